@@ -1,23 +1,93 @@
-use crate::table;
+use crate::{
+    metrics::{Kind, Metrics},
+    table, Failure,
+};
 use bollard::{
-    container::ListContainersOptions,
+    container::{ListContainersOptions, Stats, StatsOptions},
     errors,
-    secret::{ContainerSummary, Port, PortTypeEnum},
+    secret::{
+        ContainerInspectResponse, ContainerSummary, EventMessage, ImageInspect, Port, PortTypeEnum,
+        RestartPolicyNameEnum,
+    },
+    system::EventsOptions,
     Docker,
 };
-use serde::Serialize;
+use futures_util::{stream, Stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    env, fs,
+    net::IpAddr,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 #[derive(clap::Parser)]
 struct Args {
     /// Show all containers. Not only the running ones.
     #[arg(short, long)]
     all: bool,
+    /// Also show restart counts and health, which needs an inspect per container.
+    #[arg(short, long)]
+    long: bool,
+}
+
+#[derive(clap::Parser)]
+struct PortsArgs {
+    /// Only show ports that are bound to all interfaces.
+    #[arg(long)]
+    public: bool,
+}
+
+#[derive(clap::Parser)]
+struct EventsArgs {
+    /// Show events since a timestamp (unix or RFC 3339) or a duration ago (e.g. 10m).
+    #[arg(long, value_parser = parse_timestamp)]
+    since: Option<String>,
+    /// Stop streaming at a timestamp (unix or RFC 3339) or a duration ago (e.g. 10m).
+    #[arg(long, value_parser = parse_timestamp)]
+    until: Option<String>,
+    /// Filter events, e.g. `type=container,event=die`. Can be given multiple times.
+    #[arg(long, value_parser = parse_filter)]
+    filter: Vec<String>,
+    /// How to render the events.
+    #[arg(short, long, value_enum, default_value_t)]
+    output: table::Format,
+}
+
+#[derive(clap::Parser)]
+struct DoctorArgs {
+    /// Report containers that restarted more often than this.
+    #[arg(long, default_value_t = 3)]
+    max_restarts: i64,
+    /// Exit with a nonzero code when a problem of at least this severity is found.
+    #[arg(long, value_enum, default_value_t = Severity::Warning)]
+    fail_on: Severity,
+}
+
+#[derive(clap::Parser)]
+struct ComposeStatusArgs {
+    /// The compose file to compare against.
+    #[arg(short, long, default_value = "docker-compose.yml")]
+    file: PathBuf,
+    /// The compose project name. Defaults to the name in the file or its directory.
+    #[arg(short, long)]
+    project_name: Option<String>,
 }
 
 #[derive(clap::Subcommand)]
 enum Commands {
     /// Pretty print the `docker ps` output. Long branch mappings are folded up nicely. Etc..
     Ps(Args),
+    /// List all published host ports and flag exposed or conflicting ones.
+    Ports(PortsArgs),
+    /// Stream events of the docker daemon.
+    Events(EventsArgs),
+    /// Check all containers for crash loops, OOM kills, stale images and the like.
+    /// Exits with 1 when findings at or above `--fail-on` were found, for use in CI.
+    Doctor(DoctorArgs),
+    /// Compare a compose file to the containers that are actually there.
+    ComposeStatus(ComposeStatusArgs),
 }
 
 #[derive(clap::Parser)]
@@ -27,11 +97,43 @@ pub(crate) struct Cli {
 }
 
 struct DockerApi {
-    rt: tokio::runtime::Runtime,
     docker: bollard::Docker,
 }
 
+/// How many requests to the daemon may be in flight at once when fanning out.
+const CONCURRENCY: usize = 32;
+
 impl DockerApi {
+    fn events(
+        &self,
+        options: EventsOptions<String>,
+    ) -> impl Stream<Item = Result<EventMessage, errors::Error>> {
+        self.docker.events(options.into())
+    }
+
+    async fn inspect_container(&self, id: &str) -> Result<ContainerInspectResponse, errors::Error> {
+        return self.docker.inspect_container(id, None).await;
+    }
+
+    async fn inspect_image(&self, name: &str) -> Result<ImageInspect, errors::Error> {
+        return self.docker.inspect_image(name).await;
+    }
+
+    /// Inspect many containers concurrently, keyed by the given ids.
+    async fn inspect_containers(
+        &self,
+        ids: Vec<String>,
+    ) -> Result<HashMap<String, ContainerInspectResponse>, errors::Error> {
+        stream::iter(ids)
+            .map(|id| async move {
+                let container = self.inspect_container(&id).await?;
+                Ok((id, container))
+            })
+            .buffer_unordered(CONCURRENCY)
+            .try_collect()
+            .await
+    }
+
     async fn list_containers(&self, all: bool) -> Result<Vec<ContainerSummary>, errors::Error> {
         let options = ListContainersOptions::<String> {
             all,
@@ -41,16 +143,24 @@ impl DockerApi {
         return self.docker.list_containers(options.into()).await;
     }
 
+    /// A single sample of the resource usage of a running container.
+    async fn stats(&self, id: &str) -> Result<Option<Stats>, errors::Error> {
+        let options = StatsOptions {
+            stream: false,
+            one_shot: true,
+        };
+        let mut stats = std::pin::pin!(self.docker.stats(id, Some(options)));
+        stats.next().await.transpose()
+    }
+
     fn new() -> Result<Self, bollard::errors::Error> {
-        let rt = tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-            .expect("Tokio?");
         let docker = Docker::connect_with_local_defaults()?;
-        Ok(Self { rt, docker })
+        Ok(Self { docker })
     }
 }
 
+type AnyError<T> = Result<T, Box<dyn std::error::Error>>;
+
 const ID_LENGTH: usize = 12;
 const IMAGE_ID_PREFIX: &str = "sha256:";
 
@@ -64,6 +174,10 @@ struct PsRow {
     size: String,
     image: String,
     nets: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    restarts: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    health: Option<String>,
 }
 
 impl<'a> table::TableRow<'a> for PsRow {}
@@ -87,13 +201,338 @@ fn format_port(port: &Port) -> String {
     }
 }
 
+#[derive(Default, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct PortsRow {
+    port: String,
+    proto: String,
+    address: String,
+    exposure: String,
+    container: String,
+    target: String,
+    conflict: String,
+}
+
+impl<'a> table::TableRow<'a> for PortsRow {}
+
+/// Classify a host ip a port is bound to.
+fn port_exposure(ip: &IpAddr) -> &'static str {
+    if ip.is_unspecified() {
+        "public"
+    } else if ip.is_loopback() {
+        "loopback"
+    } else {
+        "interface"
+    }
+}
+
+/// A listening tcp socket on the host as found in `/proc/net/tcp{,6}`.
+struct HostListener {
+    ip: IpAddr,
+    port: u16,
+    inode: u64,
+}
+
+const TCP_LISTEN: &str = "0A";
+
+/// Parse an address of the form `0100007F:0050` as written by the kernel.
+/// The ip is a sequence of 32 bit words in host byte order.
+fn parse_proc_address(address: &str) -> Option<(IpAddr, u16)> {
+    let (ip, port) = address.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let mut bytes = vec![];
+    for i in (0..ip.len()).step_by(8) {
+        let word = u32::from_str_radix(ip.get(i..i + 8)?, 16).ok()?;
+        bytes.extend(word.to_ne_bytes());
+    }
+    let ip = match bytes.len() {
+        4 => IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?),
+        16 => IpAddr::from(<[u8; 16]>::try_from(bytes).ok()?),
+        _ => return None,
+    };
+    Some((ip, port))
+}
+
+fn host_listeners() -> Vec<HostListener> {
+    let mut listeners = vec![];
+    for path in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        for line in content.lines().skip(1) {
+            let fields: Vec<_> = line.split_whitespace().collect();
+            if fields.get(3) != Some(&TCP_LISTEN) {
+                continue;
+            }
+            let address = fields.get(1).and_then(|a| parse_proc_address(a));
+            let inode = fields.get(9).and_then(|i| i.parse().ok());
+            if let (Some((ip, port)), Some(inode)) = (address, inode) {
+                listeners.push(HostListener { ip, port, inode });
+            }
+        }
+    }
+    listeners
+}
+
+/// Find the process names owning the given socket inodes. Sockets of processes
+/// we are not allowed to inspect are simply missing from the result.
+fn socket_owners(inodes: &[u64]) -> HashMap<u64, String> {
+    let mut owners = HashMap::new();
+    let Ok(procs) = fs::read_dir("/proc") else {
+        return owners;
+    };
+    for proc in procs.flatten() {
+        let Ok(fds) = fs::read_dir(proc.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(link) = fs::read_link(fd.path()) else {
+                continue;
+            };
+            let link = link.to_string_lossy();
+            let inode = link
+                .strip_prefix("socket:[")
+                .and_then(|l| l.strip_suffix(']'))
+                .and_then(|i| i.parse::<u64>().ok());
+            if let Some(inode) = inode.filter(|i| inodes.contains(i)) {
+                let comm = fs::read_to_string(proc.path().join("comm")).unwrap_or_default();
+                owners.insert(inode, comm.trim().to_string());
+            }
+        }
+    }
+    owners
+}
+
+/// Docker publishes ports via its own proxy, those are not conflicts.
+const DOCKER_PROXY: &str = "docker-proxy";
+
+/// Accept timestamps the way `docker events` does. Durations are relative to now.
+fn parse_timestamp(value: &str) -> Result<String, String> {
+    let time = if let Ok(duration) = humantime::parse_duration(value) {
+        SystemTime::now() - duration
+    } else if let Ok(time) = humantime::parse_rfc3339_weak(value) {
+        time
+    } else if value.parse::<f64>().is_ok() {
+        return Ok(value.to_string());
+    } else {
+        return Err(format!("invalid timestamp: {value}"));
+    };
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| err.to_string())?
+        .as_secs();
+    Ok(secs.to_string())
+}
+
+fn parse_filter(value: &str) -> Result<String, String> {
+    for filter in value.split(',') {
+        if !filter.contains('=') {
+            return Err(format!("invalid filter, expected key=value: {filter}"));
+        }
+    }
+    Ok(value.to_string())
+}
+
+/// Group the already validated `key=value` filters by key.
+fn group_filters(filters: &[String]) -> HashMap<String, Vec<String>> {
+    let mut grouped: HashMap<String, Vec<String>> = HashMap::new();
+    for filter in filters.iter().flat_map(|f| f.split(',')) {
+        if let Some((key, value)) = filter.split_once('=') {
+            grouped.entry(key.into()).or_default().push(value.into());
+        }
+    }
+    grouped
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct EventRow {
+    time: String,
+    #[serde(rename = "TYPE")]
+    typ: String,
+    action: String,
+    name: String,
+    id: String,
+    exit: String,
+}
+
+impl<'a> table::TableRow<'a> for EventRow {}
+
+/// The widths to align live event rows by. Long names just push the row.
+const EVENT_COLUMN_WIDTHS: [usize; 6] = [20, 9, 12, 24, ID_LENGTH, 4];
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct DoctorRow {
+    severity: Severity,
+    container: String,
+    check: String,
+    detail: String,
+}
+
+impl<'a> table::TableRow<'a> for DoctorRow {}
+
+/// Run all checks against a single container. `latest_image` is the id the
+/// containers image name currently resolves to locally.
+fn diagnose(
+    container: &ContainerInspectResponse,
+    latest_image: Option<&str>,
+    max_restarts: i64,
+) -> Vec<(Severity, &'static str, String)> {
+    let mut problems = vec![];
+    let restarts = container.restart_count.unwrap_or_default();
+    if restarts > max_restarts {
+        problems.push((
+            Severity::Error,
+            "restarts",
+            format!("restarted {restarts} times"),
+        ));
+    }
+    if let Some(state) = &container.state {
+        if state.oom_killed == Some(true) {
+            problems.push((Severity::Error, "oom", "killed by the OOM killer".into()));
+        }
+        let exit_code = state.exit_code.unwrap_or_default();
+        if state.running != Some(true) && exit_code != 0 {
+            problems.push((
+                Severity::Warning,
+                "exit",
+                format!("exited with code {exit_code}"),
+            ));
+        }
+    }
+    let image = container.config.as_ref().and_then(|c| c.image.as_deref());
+    if let (Some(image), Some(current), Some(latest)) =
+        (image, container.image.as_deref(), latest_image)
+    {
+        if current != latest {
+            problems.push((
+                Severity::Warning,
+                "image",
+                format!("{image} has a newer local image"),
+            ));
+        }
+    }
+    let policy = container
+        .host_config
+        .as_ref()
+        .and_then(|c| c.restart_policy.as_ref())
+        .and_then(|p| p.name)
+        .unwrap_or(RestartPolicyNameEnum::EMPTY);
+    if let RestartPolicyNameEnum::EMPTY | RestartPolicyNameEnum::NO = policy {
+        problems.push((Severity::Info, "policy", "no restart policy".into()));
+    }
+    problems
+}
+
+#[derive(Deserialize)]
+struct ComposeFile {
+    name: Option<String>,
+    #[serde(default)]
+    services: BTreeMap<String, ComposeService>,
+}
+
+#[derive(Deserialize)]
+struct ComposeService {
+    image: Option<String>,
+}
+
+const COMPOSE_PROJECT_LABEL: &str = "com.docker.compose.project";
+const COMPOSE_SERVICE_LABEL: &str = "com.docker.compose.service";
+
+/// Derive the project name like compose does: lowercase and only `[a-z0-9_-]`.
+fn normalize_project_name(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect()
+}
+
+/// Substitute `${VAR}`, `${VAR:-default}` and `${VAR-default}` from the environment.
+fn interpolate(value: &str) -> String {
+    let mut output = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        // Without the closing brace the rest stays as it is.
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let expr = &rest[start + 2..start + end];
+        let (var, default, empty_is_unset) = if let Some((var, default)) = expr.split_once(":-") {
+            (var, default, true)
+        } else if let Some((var, default)) = expr.split_once('-') {
+            (var, default, false)
+        } else {
+            (expr, "", false)
+        };
+        match env::var(var) {
+            Ok(val) if !(empty_is_unset && val.is_empty()) => output.push_str(&val),
+            _ => output.push_str(default),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Bring image references into one form, so `nginx` equals `docker.io/library/nginx:latest`.
+fn normalize_image(image: &str) -> String {
+    let image = image.trim_start_matches("docker.io/");
+    let image = image.trim_start_matches("library/");
+    let name = image.rsplit('/').next().unwrap_or(image);
+    if name.contains(':') || name.contains('@') {
+        image.to_string()
+    } else {
+        format!("{image}:latest")
+    }
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct ComposeStatusRow {
+    service: String,
+    status: String,
+    container: String,
+    expected: String,
+    running: String,
+}
+
+impl<'a> table::TableRow<'a> for ComposeStatusRow {}
+
 impl Cli {
-    fn ps(&self, cmd: &DockerApi, args: &Args) -> Result<(), bollard::errors::Error> {
-        let containers = cmd.rt.block_on(cmd.list_containers(args.all))?;
+    async fn ps(&self, cmd: &DockerApi, args: &Args) -> Result<(), bollard::errors::Error> {
+        let containers = cmd.list_containers(args.all).await?;
+        let mut inspected = HashMap::new();
+        if args.long {
+            let ids = containers.iter().filter_map(|c| c.id.clone()).collect();
+            inspected = cmd.inspect_containers(ids).await?;
+        }
         let mut rows: Vec<_> = Vec::with_capacity(containers.len());
         for container in containers {
             let mut row = PsRow::default();
+            if args.long {
+                row.restarts = Some(String::new());
+                row.health = Some(String::new());
+            }
             if let Some(id) = container.id {
+                if let Some(inspect) = inspected.remove(&id) {
+                    row.restarts = Some(inspect.restart_count.unwrap_or_default().to_string());
+                    row.health = inspect
+                        .state
+                        .and_then(|s| s.health)
+                        .and_then(|h| h.status)
+                        .map(|s| s.to_string())
+                        .or(Some(String::new()));
+                }
                 row.id = id.chars().take(ID_LENGTH).collect();
             }
             if let Some(names) = container.names {
@@ -108,6 +547,10 @@ impl Cli {
                     }
                     let mut row = PsRow::default();
                     row.ports = format_port(&port);
+                    if args.long {
+                        row.restarts = Some(String::new());
+                        row.health = Some(String::new());
+                    }
                     collapsed.push(row);
                 }
             }
@@ -140,13 +583,396 @@ impl Cli {
         Ok(())
     }
 
-    pub(crate) fn run(&self) -> Result<(), bollard::errors::Error> {
+    async fn ports(&self, cmd: &DockerApi, args: &PortsArgs) -> Result<(), bollard::errors::Error> {
+        let containers = cmd.list_containers(false).await?;
+        let mut published = vec![];
+        for container in containers {
+            let name = container
+                .names
+                .unwrap_or_default()
+                .iter()
+                .map(|name| name.trim_start_matches('/'))
+                .collect::<Vec<_>>()
+                .join(", ");
+            for port in container.ports.unwrap_or_default() {
+                let (Some(ip), Some(public)) = (&port.ip, port.public_port) else {
+                    continue;
+                };
+                let Ok(ip) = ip.parse::<IpAddr>() else {
+                    continue;
+                };
+                if args.public && !ip.is_unspecified() {
+                    continue;
+                }
+                published.push((public, port.typ, ip, name.clone(), port.private_port));
+            }
+        }
+        published.sort();
+
+        let listeners = host_listeners();
+        let inodes: Vec<_> = listeners
+            .iter()
+            .filter(|l| published.iter().any(|p| p.0 == l.port))
+            .map(|l| l.inode)
+            .collect();
+        let owners = socket_owners(&inodes);
+
+        let mut rows = vec![];
+        for (public, typ, ip, name, private) in published {
+            let proto = match typ.unwrap_or(PortTypeEnum::EMPTY) {
+                PortTypeEnum::UDP => "udp",
+                PortTypeEnum::SCTP => "sctp",
+                _ => "tcp",
+            };
+            let mut conflicts = vec![];
+            if proto == "tcp" {
+                for listener in listeners.iter().filter(|l| l.port == public) {
+                    let overlaps =
+                        ip.is_unspecified() || listener.ip.is_unspecified() || ip == listener.ip;
+                    let owner = owners.get(&listener.inode).map(String::as_str);
+                    // Without root the owner of docker-proxy is hidden, then its
+                    // listener is the one on the published address itself.
+                    let own_binding = owner.is_none()
+                        && (listener.ip == ip
+                            || listener.ip.is_unspecified() && ip.is_unspecified());
+                    if !overlaps || owner == Some(DOCKER_PROXY) || own_binding {
+                        continue;
+                    }
+                    conflicts.push(format!(
+                        "{} ({})",
+                        listener.ip,
+                        owner.unwrap_or("unverified")
+                    ));
+                }
+            }
+            rows.push(PortsRow {
+                port: public.to_string(),
+                proto: proto.into(),
+                address: ip.to_string(),
+                exposure: port_exposure(&ip).into(),
+                container: name,
+                target: private.to_string(),
+                conflict: conflicts.join(", "),
+            });
+        }
+        println!("{}", table::Renderer::default().to_string(&rows));
+        Ok(())
+    }
+
+    async fn events(
+        &self,
+        cmd: &DockerApi,
+        args: &EventsArgs,
+    ) -> Result<(), bollard::errors::Error> {
+        let options = EventsOptions::<String> {
+            since: args.since.clone(),
+            until: args.until.clone(),
+            filters: group_filters(&args.filter),
+        };
+
+        let mut names: HashMap<String, String> = HashMap::new();
+        for container in cmd.list_containers(true).await? {
+            if let (Some(id), Some(name)) =
+                (container.id, container.names.unwrap_or_default().first())
+            {
+                names.insert(id, name.trim_start_matches('/').to_string());
+            }
+        }
+
+        let mut renderer = table::LiveRenderer::new(EVENT_COLUMN_WIDTHS.to_vec());
+        let mut events = std::pin::pin!(cmd.events(options));
+        while let Some(event) = events.next().await {
+            let event = event?;
+            let actor = event.actor.unwrap_or_default();
+            let attributes = actor.attributes.unwrap_or_default();
+            let id = actor.id.unwrap_or_default();
+            if let Some(name) = attributes.get("name") {
+                names.insert(id.clone(), name.clone());
+            }
+
+            let mut row = EventRow::default();
+            if let Some(time) = event.time.and_then(|t| u64::try_from(t).ok()) {
+                let time = UNIX_EPOCH + Duration::from_secs(time);
+                row.time = humantime::format_rfc3339_seconds(time).to_string();
+            }
+            if let Some(typ) = event.typ {
+                row.typ = typ.to_string();
+            }
+            row.action = event.action.unwrap_or_default();
+            row.name = names.get(&id).cloned().unwrap_or_default();
+            row.id = id.chars().take(ID_LENGTH).collect();
+            if row.action == "die" {
+                row.exit = attributes.get("exitCode").cloned().unwrap_or_default();
+            }
+
+            match args.output {
+                table::Format::Table => println!("{}", renderer.render_row(&row)),
+                table::Format::Json => println!(
+                    "{}",
+                    serde_json::to_string(&row).expect("row should serialize")
+                ),
+                table::Format::Csv => println!("{}", renderer.render_csv_row(&row)),
+            }
+        }
+        Ok(())
+    }
+
+    async fn doctor(
+        &self,
+        cmd: &DockerApi,
+        args: &DoctorArgs,
+    ) -> AnyError<()> {
+        let ids = cmd
+            .list_containers(true)
+            .await?
+            .into_iter()
+            .filter_map(|c| c.id)
+            .collect();
+        let containers = cmd.inspect_containers(ids).await?;
+
+        let images: BTreeSet<_> = containers
+            .values()
+            .filter_map(|c| c.config.as_ref().and_then(|c| c.image.clone()))
+            .collect();
+        let latest: HashMap<_, _> = stream::iter(images)
+            .map(|image| async move {
+                // The image may be gone or only referenced by id, there is nothing newer then.
+                let id = cmd.inspect_image(&image).await.ok().and_then(|i| i.id);
+                (image, id)
+            })
+            .buffer_unordered(CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut rows = vec![];
+        for (id, container) in containers {
+            let image = container.config.as_ref().and_then(|c| c.image.as_ref());
+            let latest = image.and_then(|i| latest.get(i)).cloned().flatten();
+            let name = container.name.clone().unwrap_or(id);
+            for (severity, check, detail) in
+                diagnose(&container, latest.as_deref(), args.max_restarts)
+            {
+                rows.push(DoctorRow {
+                    severity,
+                    container: name.trim_start_matches('/').to_string(),
+                    check: check.into(),
+                    detail,
+                });
+            }
+        }
+        rows.sort_by(|a, b| {
+            b.severity
+                .cmp(&a.severity)
+                .then(a.container.cmp(&b.container))
+        });
+        println!("{}", table::Renderer::default().to_string(&rows));
+        if rows.iter().any(|row| row.severity >= args.fail_on) {
+            return Err(Failure.into());
+        }
+        Ok(())
+    }
+
+    async fn compose_status(&self, cmd: &DockerApi, args: &ComposeStatusArgs) -> AnyError<()> {
+        let compose: ComposeFile = serde_yaml::from_str(&fs::read_to_string(&args.file)?)?;
+        let project = match (&args.project_name, &compose.name) {
+            (Some(name), _) => name.clone(),
+            (None, Some(name)) => interpolate(name),
+            (None, None) => {
+                let dir = fs::canonicalize(&args.file)?;
+                let dir = dir.parent().and_then(|d| d.file_name()).unwrap_or_default();
+                dir.to_string_lossy().to_string()
+            }
+        };
+        let project = normalize_project_name(&project);
+
+        let mut containers: BTreeMap<String, Vec<ContainerSummary>> = BTreeMap::new();
+        for container in cmd.list_containers(true).await? {
+            let labels = container.labels.clone().unwrap_or_default();
+            if labels.get(COMPOSE_PROJECT_LABEL) != Some(&project) {
+                continue;
+            }
+            let service = labels
+                .get(COMPOSE_SERVICE_LABEL)
+                .cloned()
+                .unwrap_or_default();
+            containers.entry(service).or_default().push(container);
+        }
+
+        let mut rows = vec![];
+        for (service, definition) in &compose.services {
+            let expected = definition.image.as_deref().map(interpolate);
+            let Some(running) = containers.remove(service) else {
+                rows.push(ComposeStatusRow {
+                    service: service.clone(),
+                    status: "missing".into(),
+                    expected: expected.unwrap_or_else(|| "(build)".into()),
+                    ..Default::default()
+                });
+                continue;
+            };
+            for container in running {
+                let image = container.image.unwrap_or_default();
+                let status = if container.state.as_deref() != Some("running") {
+                    container.state.unwrap_or_default()
+                } else if expected
+                    .as_deref()
+                    .is_some_and(|e| normalize_image(e) != normalize_image(&image))
+                {
+                    "drift".into()
+                } else {
+                    "ok".into()
+                };
+                rows.push(ComposeStatusRow {
+                    service: service.clone(),
+                    status,
+                    container: container.names.unwrap_or_default().join(", "),
+                    expected: expected.clone().unwrap_or_else(|| "(build)".into()),
+                    running: image,
+                });
+            }
+        }
+        for (service, orphans) in containers {
+            for container in orphans {
+                rows.push(ComposeStatusRow {
+                    service: service.clone(),
+                    status: "orphan".into(),
+                    container: container.names.unwrap_or_default().join(", "),
+                    running: container.image.unwrap_or_default(),
+                    ..Default::default()
+                });
+            }
+        }
+        println!("{}", table::Renderer::default().to_string(&rows));
+        Ok(())
+    }
+
+    /// Run the command on the runtime of the caller.
+    pub(crate) async fn run(&self) -> AnyError<()> {
         let api = DockerApi::new()?;
         if let Some(command) = &self.command {
             match command {
-                Commands::Ps(args) => self.ps(&api, args)?,
+                Commands::Ps(args) => self.ps(&api, args).await?,
+                Commands::Ports(args) => self.ports(&api, args).await?,
+                Commands::Events(args) => self.events(&api, args).await?,
+                Commands::Doctor(args) => self.doctor(&api, args).await?,
+                Commands::ComposeStatus(args) => self.compose_status(&api, args).await?,
             }
         }
         Ok(())
     }
 }
+
+/// Collect the docker metrics for `serve-metrics`.
+pub(crate) async fn collect_metrics(metrics: &mut Metrics) -> AnyError<()> {
+    let api = DockerApi::new()?;
+    let containers = api.list_containers(true).await?;
+
+    let mut states: BTreeMap<String, usize> = BTreeMap::new();
+    for container in &containers {
+        *states
+            .entry(container.state.clone().unwrap_or_default())
+            .or_default() += 1;
+    }
+    metrics.describe("docker_containers", Kind::Gauge, "Containers by state.");
+    for (state, count) in states {
+        metrics.sample("docker_containers", &[("state", &state)], count as f64);
+    }
+
+    let names: BTreeMap<String, String> = containers
+        .iter()
+        .filter_map(|container| {
+            let id = container.id.clone()?;
+            let name = container.names.as_ref()?.first()?;
+            Some((id, name.trim_start_matches('/').to_string()))
+        })
+        .collect();
+    let inspected = api
+        .inspect_containers(names.keys().cloned().collect())
+        .await?;
+    metrics.describe(
+        "docker_container_restarts",
+        Kind::Gauge,
+        "How often the daemon restarted a container.",
+    );
+    for (id, name) in &names {
+        let restarts = inspected
+            .get(id)
+            .and_then(|container| container.restart_count)
+            .unwrap_or_default();
+        metrics.sample(
+            "docker_container_restarts",
+            &[("name", name)],
+            restarts as f64,
+        );
+    }
+
+    let running: Vec<_> = containers
+        .iter()
+        .filter(|container| container.state.as_deref() == Some("running"))
+        .filter_map(|container| container.id.clone())
+        .collect();
+    let stats: Vec<_> = stream::iter(running)
+        .map(|id| async {
+            let stats = api.stats(&id).await?;
+            Ok::<_, errors::Error>((id, stats))
+        })
+        .buffer_unordered(CONCURRENCY)
+        .try_collect()
+        .await?;
+    let stats: Vec<_> = stats
+        .into_iter()
+        .filter_map(|(id, stats)| Some((names.get(&id)?.as_str(), stats?)))
+        .collect();
+    metrics.describe(
+        "docker_container_cpu_seconds_total",
+        Kind::Counter,
+        "CPU time used by a running container.",
+    );
+    for (name, stats) in &stats {
+        metrics.sample(
+            "docker_container_cpu_seconds_total",
+            &[("name", name)],
+            stats.cpu_stats.cpu_usage.total_usage as f64 / 1e9,
+        );
+    }
+    metrics.describe(
+        "docker_container_memory_bytes",
+        Kind::Gauge,
+        "Memory used by a running container.",
+    );
+    for (name, stats) in &stats {
+        metrics.sample(
+            "docker_container_memory_bytes",
+            &[("name", name)],
+            stats.memory_stats.usage.unwrap_or_default() as f64,
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolate_keeps_unclosed_variables() {
+        assert_eq!(interpolate("app-${TAG"), "app-${TAG");
+        assert_eq!(
+            interpolate("app-${BINS_TEST_UNSET:-1.0}-${TAG"),
+            "app-1.0-${TAG"
+        );
+    }
+
+    #[test]
+    fn normalize_image_fills_in_defaults() {
+        assert_eq!(
+            normalize_image("nginx"),
+            normalize_image("docker.io/library/nginx:latest")
+        );
+        assert_eq!(normalize_image("ghcr.io/org/app"), "ghcr.io/org/app:latest");
+        assert_eq!(
+            normalize_image("localhost:5000/app"),
+            "localhost:5000/app:latest"
+        );
+    }
+}
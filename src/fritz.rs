@@ -3,21 +3,167 @@ use core::fmt;
 use md5::{digest::FixedOutputReset, Digest, Md5};
 use reqwest as rw;
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    net::Ipv4Addr,
+    thread,
+    time::{Duration, Instant},
+};
+
+mod access;
+mod backup;
+mod calls;
+mod ddns;
+mod device;
+mod discover;
+mod dsl;
+mod eventlog;
+mod mesh;
+mod metrics;
+mod portmapping;
+mod presence;
+mod smarthome;
+mod tr064;
+mod traffic;
+mod update;
+mod wan;
+mod wlan;
+
+pub(crate) use metrics::Exporter;
 
 #[derive(clap::Parser)]
-struct Args {}
+struct Args {
+    /// Also ask the box at this URL, e.g. a repeater with the same login.
+    #[arg(long = "node", value_name = "URL")]
+    nodes: Vec<String>,
+    /// Also ask the repeaters in the mesh of the box.
+    #[arg(long)]
+    mesh: bool,
+}
+
+#[derive(clap::Parser)]
+struct DevicesArgs {
+    #[command(flatten)]
+    targets: Args,
+    /// The interface to list the devices with.
+    #[arg(long, value_enum, default_value_t)]
+    backend: Backend,
+}
+
+#[derive(clap::Parser)]
+struct WaitArgs {
+    /// The interface to send the command with.
+    #[arg(long, value_enum, default_value_t)]
+    backend: Backend,
+    /// Wait until the box and its internet connection are back.
+    #[arg(long)]
+    wait: bool,
+    /// Give up waiting after this long.
+    #[arg(long, default_value = "10m")]
+    timeout: humantime::Duration,
+}
+
+/// Which interface of the box to talk to.
+#[derive(Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+enum Backend {
+    /// The documented TR-064 SOAP interface.
+    Tr064,
+    /// The endpoints of the web UI.
+    #[default]
+    Web,
+}
 
 #[derive(clap::Subcommand)]
 enum Commands {
     /// Display debugging information about the FritzBox.
     Info(Args),
     /// Reboot the device instantly.
-    Reboot(Args),
+    Reboot(WaitArgs),
     /// Reconnect the device, usually with a new IP.
-    Reconnect(Args),
+    Reconnect(WaitArgs),
     /// List all known devices and device info.
-    Devices(Args),
+    Devices(DevicesArgs),
+    /// Discover and invoke any TR-064 action.
+    #[command(arg_required_else_help(true))]
+    Tr064(tr064::Cli),
+    /// List and control smart home devices like DECT plugs and thermostats.
+    #[command(arg_required_else_help(true))]
+    Smarthome(smarthome::Cli),
+    /// Export the call list.
+    Calls(calls::Args),
+    /// Show the wireless networks, toggle the guest network and share access.
+    #[command(arg_required_else_help(true))]
+    Wlan(wlan::Cli),
+    /// List and edit port sharing of devices.
+    #[command(arg_required_else_help(true))]
+    Ports(portmapping::Cli),
+    /// Show the traffic counters or watch the current throughput.
+    Traffic(traffic::Args),
+    /// Show the DSL line statistics and error counters.
+    Dsl(dsl::Args),
+    /// Show or follow the event log.
+    Log(eventlog::Args),
+    /// Show the details of a device or rename it.
+    Device(device::Cli),
+    /// Wake a device up over the LAN.
+    Wol(device::WolArgs),
+    /// Block the internet access of a device.
+    Block(access::Args),
+    /// Allow the internet access of a device again.
+    Unblock(access::Args),
+    /// List the access profiles and the devices using them.
+    Profiles(access::ProfilesArgs),
+    /// Save an export of all settings.
+    Backup(backup::BackupArgs),
+    /// Overwrite all settings with an export.
+    Restore(backup::RestoreArgs),
+    /// Check for and install firmware updates.
+    #[command(arg_required_else_help(true))]
+    Update(update::Cli),
+    /// Show the external addresses, DNS servers and uptime.
+    Ip(wan::Args),
+    /// Push the external IP to a dynamic DNS provider when it changed.
+    Ddns(ddns::Args),
+    /// Track devices joining and leaving the network.
+    #[command(arg_required_else_help(true))]
+    Presence(presence::Cli),
+    /// Print the mesh of the box and its repeaters as a tree.
+    Mesh(mesh::Args),
+    /// Find the boxes on the network.
+    Discover(discover::Args),
+}
+
+impl Commands {
+    /// Whether the command needs a web UI session.
+    fn needs_login(&self) -> bool {
+        match self {
+            Self::Reboot(args) | Self::Reconnect(args) => args.backend == Backend::Web,
+            Self::Devices(args) => args.backend == Backend::Web,
+            Self::Wlan(cli) => cli.needs_login(),
+            Self::Tr064(_)
+            | Self::Dsl(_)
+            | Self::Update(_)
+            | Self::Ip(_)
+            | Self::Ddns(_)
+            | Self::Mesh(_)
+            | Self::Discover(_) => false,
+            Self::Info(_)
+            | Self::Smarthome(_)
+            | Self::Calls(_)
+            | Self::Ports(_)
+            | Self::Traffic(_)
+            | Self::Log(_)
+            | Self::Device(_)
+            | Self::Wol(_)
+            | Self::Block(_)
+            | Self::Unblock(_)
+            | Self::Profiles(_)
+            | Self::Backup(_)
+            | Self::Restore(_)
+            | Self::Presence(_) => true,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -46,11 +192,47 @@ impl Session {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
 struct Config<'a> {
     base_url: &'a str,
     username: &'a str,
     password: &'a str,
+    tr064_url: Option<&'a str>,
+}
+
+/// The settings from the `FRITZ_*` env vars, which the config borrows.
+pub(crate) struct Env {
+    base_url: String,
+    username: String,
+    password: String,
+    tr064_url: Option<String>,
+}
+
+impl Env {
+    pub(crate) fn from_env() -> AnyError<Self> {
+        let var = |name: &str| env::var(name).map_err(|_| format!("expected {name} env var"));
+        let username = var("FRITZ_USER")?;
+        let password = var("FRITZ_PASSWORD")?;
+        let base_url = match env::var("FRITZ_URL") {
+            Ok(url) => url,
+            Err(_) => discover::default_url()?,
+        };
+        Ok(Self {
+            base_url,
+            username,
+            password,
+            tr064_url: env::var("FRITZ_TR064_URL").ok(),
+        })
+    }
+
+    fn config(&self) -> Config<'_> {
+        Config {
+            base_url: self.base_url.as_str(),
+            username: self.username.as_str(),
+            password: self.password.as_str(),
+            tr064_url: self.tr064_url.as_deref(),
+        }
+    }
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -129,6 +311,123 @@ impl Devices {
         let passive = d.passive.into_iter().flatten();
         return active.chain(passive).into();
     }
+
+    /// Find a device by its name, IP, MAC or UID.
+    fn find(self, query: &str) -> Option<Device> {
+        self.devices()?.find(|device| {
+            device.uid == query
+                || device.name.as_deref() == Some(query)
+                || device.ipv4.as_ref().is_some_and(|ipv4| ipv4.ip == query)
+                || device
+                    .mac
+                    .as_ref()
+                    .is_some_and(|mac| mac.eq_ignore_ascii_case(query))
+        })
+    }
+}
+
+/// A local date and time as the box writes it, e.g. `18.10.26` and `12:34`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct BoxTime {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+impl BoxTime {
+    /// Parse a `dd.mm.yy` date and a `HH:MM[:SS]` time.
+    fn parse(date: &str, time: &str) -> Option<Self> {
+        let mut date = date.trim().split('.');
+        let day = date.next()?.parse().ok()?;
+        let month = date.next()?.parse().ok()?;
+        let year: u16 = date.next()?.parse().ok()?;
+        let mut time = time.trim().split(':');
+        let hour = time.next()?.parse().ok()?;
+        let minute = time.next()?.parse().ok()?;
+        let second = time.next().map(str::parse).transpose().ok()?.unwrap_or(0);
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        if hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        Some(Self {
+            year: if year < 100 { 2000 + year } else { year },
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+}
+
+impl fmt::Display for BoxTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute
+        )
+    }
+}
+
+/// Parse `YYYY-MM-DD` or `YYYY-MM-DD HH:MM` for `--since` style arguments.
+fn parse_since(value: &str) -> Result<BoxTime, String> {
+    let invalid = || format!("invalid date, expected YYYY-MM-DD [HH:MM]: {value}");
+    let (date, time) = value.trim().split_once(' ').unwrap_or((value, "00:00"));
+    let mut parts = date.split('-');
+    let (Some(year), Some(month), Some(day), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    BoxTime::parse(&format!("{day}.{month}.{year}"), time).ok_or_else(invalid)
+}
+
+/// The shortest `--interval` to poll the box with.
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Parse a polling `--interval`, not so short that it hammers the box.
+fn parse_interval(value: &str) -> Result<humantime::Duration, String> {
+    let interval: humantime::Duration = value.parse().map_err(|err| format!("{err}"))?;
+    if Duration::from(interval) < MIN_INTERVAL {
+        return Err(format!(
+            "interval must be at least {}",
+            humantime::format_duration(MIN_INTERVAL)
+        ));
+    }
+    Ok(interval)
+}
+
+/// The state of the internet LED on the overview once connected.
+const INTERNET_ONLINE: &str = "globe_online";
+
+/// How long a reconnect without `--wait` looks out for the new IP.
+const RECONNECT_GRACE: Duration = Duration::from_secs(30);
+
+/// How often to ask a box that is on its way back.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Poll until `check` gives a value, e.g. while the box reboots.
+fn wait_for<T>(deadline: Instant, mut check: impl FnMut() -> Option<T>) -> AnyError<T> {
+    loop {
+        if let Some(value) = check() {
+            return Ok(value);
+        }
+        if Instant::now() >= deadline {
+            return Err("Timed out waiting for the box!".into());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Elapsed time in whole seconds, e.g. `2m 5s`.
+fn format_elapsed(elapsed: Duration) -> String {
+    humantime::format_duration(Duration::from_secs(elapsed.as_secs())).to_string()
 }
 
 // TODO: I dont i need this trait anymore.
@@ -191,6 +490,60 @@ impl<'a> FritzClient<'a> {
             session: Session::default(),
         }
     }
+
+    /// Like `overview`, without announcing every poll.
+    fn poll_overview(&self) -> AnyError<Overview> {
+        let res = self.data_lua("overview", &[])?;
+        let overview: Overview = serde_json::from_str(&res)?;
+        Ok(overview)
+    }
+
+    /// Like `devices`, without announcing every poll.
+    fn poll_devices(&self) -> AnyError<Devices> {
+        let res = self.data_lua("netDev", &[("xhrId", "all")])?;
+        let devices: Devices = serde_json::from_str(&res)?;
+        Ok(devices)
+    }
+
+    /// Whether the box answers at all, without logging in.
+    fn is_reachable(&self) -> bool {
+        self.client
+            .get(format!("{base}/login_sid.lua", base = self.config.base_url))
+            .timeout(POLL_INTERVAL)
+            .send()
+            .is_ok_and(|res| res.status().is_success())
+    }
+
+    /// Whether the internet connection is up. Logs in anew, as sessions do not
+    /// survive a reboot.
+    fn is_online(&self) -> bool {
+        let mut api = FritzClient::new_with_config(self.config.clone());
+        api.login().is_ok()
+            && api
+                .poll_overview()
+                .is_ok_and(|overview| overview.data.internet.state == INTERNET_ONLINE)
+    }
+
+    /// Post a page request to `data.lua` the way the web UI does.
+    fn data_lua(&self, page: &str, fields: &[(&str, &str)]) -> AnyError<String> {
+        let res = self
+            .client
+            .post(format!("{base}/data.lua", base = self.config.base_url))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(form_data!(
+                &[("sid", self.session.sid.as_str()), ("page", page)],
+                fields
+            ))
+            .send()?
+            .error_for_status()?
+            .text()?;
+        Ok(res)
+    }
+
+    /// Connect to the TR-064 interface with the same credentials.
+    fn tr064(&self) -> AnyError<tr064::Tr064Client> {
+        tr064::Tr064Client::discover(&self.config)
+    }
 }
 
 impl<'a> FritzApiFunctions for FritzClient<'a> {
@@ -350,6 +703,7 @@ struct DevicesRow {
     ip: String,
     lastused: String,
     connection: String,
+    node: String,
     model: String,
     uid: String,
     trusted: String,
@@ -361,6 +715,7 @@ impl<'a> table::TableRow<'a> for DevicesRow {}
 #[derive(Default, Serialize)]
 #[serde(rename_all = "UPPERCASE")]
 struct OverviewRow {
+    node: String,
     model: String,
     version: String,
     name: String,
@@ -371,12 +726,54 @@ struct OverviewRow {
 
 impl<'a> table::TableRow<'a> for OverviewRow {}
 
+/// The host of a box, to tell the boxes apart.
+fn node_name(url: &str) -> String {
+    rw::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(String::from))
+        .unwrap_or_else(|| url.to_string())
+}
+
 impl Cli {
-    fn info(&self, api: &FritzClient, _args: &Args) -> AnyError<()> {
-        let overview = api.overview()?;
+    /// Run `f` for the box and then for every node, each node with a session of
+    /// its own when the box has one.
+    fn for_each_node<T>(
+        &self,
+        api: &FritzClient,
+        args: &Args,
+        mut f: impl FnMut(&FritzClient) -> AnyError<T>,
+    ) -> AnyError<Vec<T>> {
+        let mut urls = args.nodes.clone();
+        if args.mesh {
+            urls.extend(mesh::repeater_urls(api)?);
+        }
+        let mut results = vec![f(api)?];
+        for url in &urls {
+            let mut node = FritzClient::new_with_config(Config {
+                base_url: url,
+                tr064_url: None,
+                ..api.config.clone()
+            });
+            if !api.session.is_default_sid() {
+                node.login()?;
+            }
+            results.push(f(&node)?);
+        }
+        Ok(results)
+    }
+
+    fn info(&self, api: &FritzClient, args: &Args) -> AnyError<()> {
+        let rows = self.for_each_node(api, args, |api| {
+            Ok(Self::overview_row(api.config.base_url, api.overview()?))
+        })?;
+        println!("{}", table::Renderer::default().to_string(&rows));
+        Ok(())
+    }
 
+    fn overview_row(url: &str, overview: Overview) -> OverviewRow {
         let os = overview.data.os;
         let mut row = OverviewRow::default();
+        row.node = node_name(url);
         row.model = os.product_name;
         row.version = os.version;
         row.name = os.name;
@@ -397,13 +794,14 @@ impl Cli {
             up = inet.up,
             down = inet.down
         );
-
-        println!("{}", table::Renderer::default().to_string(&[row]));
-        Ok(())
+        row
     }
 
-    fn reboot(&self, api: &FritzClient, _args: &Args) -> AnyError<()> {
-        let ok = api.reboot()?;
+    fn reboot(&self, api: &FritzClient, args: &WaitArgs) -> AnyError<()> {
+        let ok = match args.backend {
+            Backend::Web => api.reboot()?,
+            Backend::Tr064 => api.tr064()?.reboot()?,
+        };
         println!(
             "Reboot status: {}",
             match ok {
@@ -411,22 +809,128 @@ impl Cli {
                 _ => "No Reboot!",
             }
         );
+        if !(ok && args.wait) {
+            return Ok(());
+        }
+        let start = Instant::now();
+        let deadline = start + Duration::from(args.timeout);
+        // The box takes a moment to go down, don't mistake that for being back.
+        wait_for(deadline, || (!api.is_reachable()).then_some(()))?;
+        wait_for(deadline, || api.is_reachable().then_some(()))?;
+        println!("The box is back after {}.", format_elapsed(start.elapsed()));
+        wait_for(deadline, || api.is_online().then_some(()))?;
+        println!(
+            "The internet is back after {}.",
+            format_elapsed(start.elapsed())
+        );
         Ok(())
     }
 
-    fn reconnect(&self, api: &FritzClient, _args: &Args) -> rw::Result<()> {
-        api.disconnect()?;
-        api.connect()?;
-        println!("Heads up! This can take up to 30s to take full effect..");
+    fn reconnect(&self, api: &FritzClient, args: &WaitArgs) -> AnyError<()> {
+        // Only to show the old and new IP, the web backend works without TR-064.
+        let old_ip = api.tr064().and_then(|client| client.external_ip()).ok();
+        match args.backend {
+            Backend::Web => {
+                api.disconnect()?;
+                api.connect()?;
+            }
+            Backend::Tr064 => {
+                let tr064 = api.tr064()?;
+                tr064.disconnect()?;
+                tr064.connect()?;
+            }
+        }
+        let Some(old_ip) = old_ip else {
+            println!("Heads up! This can take up to 30s to take full effect..");
+            if args.wait {
+                let start = Instant::now();
+                wait_for(start + Duration::from(args.timeout), || {
+                    api.is_online().then_some(())
+                })?;
+                println!(
+                    "The internet is back after {}.",
+                    format_elapsed(start.elapsed())
+                );
+            }
+            return Ok(());
+        };
+        let start = Instant::now();
+        let external_ip = || {
+            let ip = api.tr064().ok()?.external_ip().ok()?;
+            // Disconnected boxes report `0.0.0.0`.
+            let connected = ip.parse::<Ipv4Addr>().is_ok_and(|ip| !ip.is_unspecified());
+            connected.then_some(ip)
+        };
+        if !args.wait {
+            match wait_for(start + RECONNECT_GRACE, || {
+                external_ip().filter(|ip| *ip != old_ip)
+            }) {
+                Ok(new_ip) => println!("{old_ip} → {new_ip}"),
+                Err(_) => println!(
+                    "Heads up! Still on {old_ip}, this can take some time to take full effect.."
+                ),
+            }
+            return Ok(());
+        }
+        // Static IPs come back unchanged. Those only count once the connection was
+        // seen down or had its grace to drop, not to take the old state for done.
+        let mut went_down = false;
+        let new_ip = wait_for(start + Duration::from(args.timeout), || {
+            let ip = external_ip();
+            went_down |= ip.is_none();
+            let ip = ip?;
+            let settled = ip != old_ip || went_down || start.elapsed() >= RECONNECT_GRACE;
+            (settled && api.is_online()).then_some(ip)
+        })?;
+        let elapsed = format_elapsed(start.elapsed());
+        match new_ip == old_ip {
+            true => println!(
+                "The internet is back after {elapsed}: {old_ip} → {new_ip}, the IP did not change."
+            ),
+            false => println!("The internet is back after {elapsed}: {old_ip} → {new_ip}"),
+        }
         Ok(())
     }
 
-    fn devices(&self, api: &FritzClient, _args: &Args) -> AnyError<()> {
-        let data = api.devices()?;
+    fn devices(&self, api: &FritzClient, args: &DevicesArgs) -> AnyError<()> {
+        let lists = self.for_each_node(api, &args.targets, |api| {
+            let data = match args.backend {
+                Backend::Web => api.devices()?,
+                Backend::Tr064 => api.tr064()?.devices()?,
+            };
+            Ok((node_name(api.config.base_url), data))
+        })?;
+        // Only with several boxes, and not every box has a mesh. Otherwise the
+        // box listing a device is the best guess.
+        let access_points = match args.targets.mesh || !args.targets.nodes.is_empty() {
+            true => api
+                .tr064()
+                .and_then(|client| client.mesh())
+                .map(|topology| topology.access_points())
+                .unwrap_or_default(),
+            false => HashMap::new(),
+        };
+        let mut seen = HashSet::new();
         let mut rows = vec![];
-        if let Some(devices) = data.devices() {
+        for (node, data) in lists {
+            // Repeaters list the devices of the whole mesh again, only the MAC
+            // tells them apart across boxes.
+            let devices = data.devices().into_iter().flatten().filter(|device| {
+                device
+                    .mac
+                    .as_ref()
+                    .is_none_or(|mac| seen.insert(mac.to_uppercase()))
+            });
             for device in devices {
-                let mut row = DevicesRow::default();
+                let mut row = DevicesRow {
+                    node: device
+                        .mac
+                        .as_ref()
+                        .and_then(|mac| access_points.get(&mac.to_uppercase()))
+                        .unwrap_or(&node)
+                        .clone(),
+                    ..Default::default()
+                };
                 if let Some(name) = device.name {
                     row.name = name;
                 }
@@ -458,31 +962,42 @@ impl Cli {
 
     pub(crate) fn run(&self) -> AnyError<()> {
         if let Some(command) = &self.command {
+            // Finding the box must not depend on knowing it already.
+            if let Commands::Discover(args) = command {
+                return discover::run(args);
+            }
             // TODO: save sid with expire date to session.json and reuse?
-            let base_url = env::var_os("FRITZ_URL")
-                .expect("expected FRITZ_URL env var")
-                .into_string()
-                .expect("expected FRITZ_URL env var to be valid");
-            let username = env::var_os("FRITZ_USER")
-                .expect("expected FRITZ_USER env var")
-                .into_string()
-                .expect("expected FRITZ_USER env var to be valid");
-            let password = env::var_os("FRITZ_PASSWORD")
-                .expect("expected FRITZ_PASSWORD env var")
-                .into_string()
-                .expect("expected FRITZ_PASSWORD env var to be valid");
-            let config = Config {
-                base_url: base_url.as_str(),
-                username: username.as_str(),
-                password: password.as_str(),
-            };
-            let mut api = FritzClient::new_with_config(config);
-            api.login()?;
+            let env = Env::from_env()?;
+            let mut api = FritzClient::new_with_config(env.config());
+            if command.needs_login() {
+                api.login()?;
+            }
             match command {
                 Commands::Info(args) => self.info(&api, args)?,
                 Commands::Reboot(args) => self.reboot(&api, args)?,
                 Commands::Reconnect(args) => self.reconnect(&api, args)?,
                 Commands::Devices(args) => self.devices(&api, args)?,
+                Commands::Tr064(cli) => cli.run(&api)?,
+                Commands::Smarthome(cli) => cli.run(&api)?,
+                Commands::Calls(args) => calls::run(&api, args)?,
+                Commands::Wlan(cli) => cli.run(&api)?,
+                Commands::Ports(cli) => cli.run(&api)?,
+                Commands::Traffic(args) => traffic::run(&api, args)?,
+                Commands::Dsl(args) => dsl::run(&api, args)?,
+                Commands::Log(args) => eventlog::run(&api, args)?,
+                Commands::Device(cli) => cli.run(&api)?,
+                Commands::Wol(args) => device::wol(&api, args)?,
+                Commands::Block(args) => access::block(&api, args, true)?,
+                Commands::Unblock(args) => access::block(&api, args, false)?,
+                Commands::Profiles(args) => access::profiles(&api, args)?,
+                Commands::Backup(args) => backup::backup(&api, args)?,
+                Commands::Restore(args) => backup::restore(&api, args)?,
+                Commands::Update(cli) => cli.run(&api)?,
+                Commands::Ip(args) => wan::run(&api, args)?,
+                Commands::Ddns(args) => ddns::run(&api, args)?,
+                Commands::Presence(cli) => cli.run(&api)?,
+                Commands::Mesh(args) => mesh::run(&api, args)?,
+                Commands::Discover(_) => unreachable!("discover runs without a box"),
             }
         }
         Ok(())
@@ -0,0 +1,129 @@
+use super::{AnyError, FritzClient};
+use crate::table;
+use serde::{Deserialize, Serialize};
+
+const HOST_FILTER_SERVICE: &str = "X_AVM-DE_HostFilter:1";
+
+#[derive(Debug, Default, Deserialize)]
+struct Profile {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProfilesData {
+    #[serde(default)]
+    profiles: Vec<Profile>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Profiles {
+    data: ProfilesData,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProfileDevice {
+    name: String,
+    /// The id of the profile in use.
+    profile: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProfileDevicesData {
+    #[serde(default)]
+    devices: Vec<ProfileDevice>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProfileDevices {
+    data: ProfileDevicesData,
+}
+
+impl FritzClient<'_> {
+    /// Query all access profiles.
+    fn profiles(&self) -> AnyError<Profiles> {
+        let res = self.data_lua("kidPro", &[("xhrId", "all")])?;
+        let profiles: Profiles = serde_json::from_str(&res)?;
+        Ok(profiles)
+    }
+
+    /// Query the devices with the profile they use.
+    fn profile_devices(&self) -> AnyError<ProfileDevices> {
+        let res = self.data_lua("kidLis", &[("xhrId", "all")])?;
+        let devices: ProfileDevices = serde_json::from_str(&res)?;
+        Ok(devices)
+    }
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Args {
+    /// The name, IP or MAC of the device.
+    device: String,
+}
+
+#[derive(clap::Parser)]
+pub(super) struct ProfilesArgs {
+    /// How to render the profiles.
+    #[arg(short, long, value_enum, default_value_t)]
+    output: table::Format,
+}
+
+/// Block or unblock the internet access of a device, which the box keys by IP.
+pub(super) fn block(api: &FritzClient, args: &Args, disallow: bool) -> AnyError<()> {
+    let device = api.device(&args.device)?;
+    let ip = device
+        .ipv4
+        .as_ref()
+        .map(|ipv4| ipv4.ip.as_str())
+        .ok_or_else(|| format!("Device {} has no IPv4 address!", args.device))?;
+    let client = api.tr064()?;
+    client.call(
+        HOST_FILTER_SERVICE,
+        "DisallowWANAccessByIP",
+        &[
+            ("NewIPv4Address", ip),
+            ("NewDisallow", if disallow { "1" } else { "0" }),
+        ],
+    )?;
+    let access = client.call(
+        HOST_FILTER_SERVICE,
+        "GetWANAccessByIP",
+        &[("NewIPv4Address", ip)],
+    )?;
+    println!(
+        "{} ({ip}): internet access {}",
+        device.name.as_deref().unwrap_or(&device.uid),
+        access.get("NewWANAccess").unwrap_or("unknown")
+    );
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct ProfileRow {
+    profile: String,
+    devices: String,
+}
+
+impl<'a> table::TableRow<'a> for ProfileRow {}
+
+pub(super) fn profiles(api: &FritzClient, args: &ProfilesArgs) -> AnyError<()> {
+    let devices = api.profile_devices()?.data.devices;
+    let rows: Vec<_> = api
+        .profiles()?
+        .data
+        .profiles
+        .into_iter()
+        .map(|profile| ProfileRow {
+            devices: devices
+                .iter()
+                .filter(|device| device.profile == profile.id)
+                .map(|device| device.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            profile: profile.name,
+        })
+        .collect();
+    println!("{}", table::Renderer::default().render(args.output, &rows));
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use super::{AnyError, FritzClient};
+use reqwest::blocking::multipart::{Form, Part};
+use std::{
+    fs::{self, OpenOptions, Permissions},
+    io::Write,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::PathBuf,
+    time::SystemTime,
+};
+
+const FIRMWARECFG_PATH: &str = "/cgi-bin/firmwarecfg";
+/// Every export starts with this, anything else is an error page.
+const EXPORT_HEADER: &str = "****";
+
+impl FritzClient<'_> {
+    fn firmwarecfg(&self, form: Form) -> AnyError<Vec<u8>> {
+        let res = self
+            .client
+            .post(format!(
+                "{base}{FIRMWARECFG_PATH}",
+                base = self.config.base_url
+            ))
+            .multipart(form)
+            .send()?
+            .error_for_status()?
+            .bytes()?;
+        Ok(res.to_vec())
+    }
+
+    /// Export the settings, encrypted with the password if any.
+    fn export_config(&self, password: &str) -> AnyError<Vec<u8>> {
+        let form = Form::new()
+            .text("sid", self.session.sid.clone())
+            .text("ImportExportPassword", password.to_string())
+            .text("ConfigExport", "");
+        let export = self.firmwarecfg(form)?;
+        if !export.starts_with(EXPORT_HEADER.as_bytes()) {
+            return Err("The box did not export its settings!".into());
+        }
+        Ok(export)
+    }
+
+    /// Import exported settings, the box reboots afterwards.
+    fn import_config(&self, export: Vec<u8>, password: &str) -> AnyError<()> {
+        let form = Form::new()
+            .text("sid", self.session.sid.clone())
+            .text("ImportExportPassword", password.to_string())
+            .part(
+                "ConfigImportFile",
+                Part::bytes(export).file_name("fritzbox.export"),
+            );
+        self.firmwarecfg(form)?;
+        Ok(())
+    }
+}
+
+#[derive(clap::Parser)]
+pub(super) struct BackupArgs {
+    /// Where to save the export, a directory gets a timestamped file name.
+    #[arg(long)]
+    out: Option<PathBuf>,
+    /// Without it the export can only be restored to the same box.
+    #[arg(long, default_value = "")]
+    export_password: String,
+}
+
+#[derive(clap::Parser)]
+pub(super) struct RestoreArgs {
+    /// The file saved by `backup`.
+    file: PathBuf,
+    /// The password the export was saved with.
+    #[arg(long, default_value = "")]
+    export_password: String,
+    /// Really overwrite all settings and reboot the box.
+    #[arg(long)]
+    yes: bool,
+}
+
+/// E.g. `fritz.box-20261018-023000.export`, in UTC so cron jobs sort right.
+fn default_file_name(base_url: &str) -> AnyError<String> {
+    let url = reqwest::Url::parse(base_url)?;
+    let host = url.host_str().unwrap_or("fritzbox");
+    let timestamp: String = humantime::format_rfc3339_seconds(SystemTime::now())
+        .to_string()
+        .chars()
+        .filter_map(|c| match c {
+            '-' | ':' | 'Z' => None,
+            'T' => Some('-'),
+            c => Some(c),
+        })
+        .collect();
+    Ok(format!("{host}-{timestamp}.export"))
+}
+
+pub(super) fn backup(api: &FritzClient, args: &BackupArgs) -> AnyError<()> {
+    let path = match &args.out {
+        Some(out) if !out.is_dir() => out.clone(),
+        out => out
+            .clone()
+            .unwrap_or_default()
+            .join(default_file_name(api.config.base_url)?),
+    };
+    let export = api.export_config(&args.export_password)?;
+    // The export holds the credentials of the box, keep it to ourselves.
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&path)?;
+    file.set_permissions(Permissions::from_mode(0o600))?;
+    file.write_all(&export)?;
+    println!("Saved {} bytes to {}.", export.len(), path.display());
+    Ok(())
+}
+
+pub(super) fn restore(api: &FritzClient, args: &RestoreArgs) -> AnyError<()> {
+    if !args.yes {
+        return Err("Restoring overwrites all settings and reboots the box, pass --yes!".into());
+    }
+    let export = fs::read(&args.file)?;
+    if !export.starts_with(EXPORT_HEADER.as_bytes()) {
+        return Err(format!("{} is not a settings export!", args.file.display()).into());
+    }
+    api.import_config(export, &args.export_password)?;
+    println!("Restored the settings, the box reboots now.");
+    Ok(())
+}
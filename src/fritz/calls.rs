@@ -0,0 +1,131 @@
+use super::{parse_since, AnyError, BoxTime, FritzClient};
+use crate::table;
+use serde::Serialize;
+
+const CALLS_PATH: &str = "/fon_num/foncalls_list.lua";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum CallType {
+    Incoming,
+    Missed,
+    Outgoing,
+    Rejected,
+}
+
+impl CallType {
+    /// The numeric types of the export. Calls still going on count as done.
+    fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "1" | "9" => Some(Self::Incoming),
+            "2" => Some(Self::Missed),
+            "3" | "11" => Some(Self::Outgoing),
+            "10" => Some(Self::Rejected),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Call {
+    typ: CallType,
+    date: BoxTime,
+    name: String,
+    number: String,
+    extension: String,
+    /// In minutes.
+    duration: u32,
+}
+
+/// Parse the `;` separated export. The first line declares the separator,
+/// the second one holds the (localized) column names.
+fn parse_calls(csv: &str) -> Vec<Call> {
+    let mut calls = vec![];
+    for line in csv.lines() {
+        let fields: Vec<_> = line.split(';').map(|f| f.trim_matches('"')).collect();
+        let [typ, date, name, number, extension, _own_number, duration, ..] = fields[..] else {
+            continue;
+        };
+        let Some(typ) = CallType::from_code(typ) else {
+            continue;
+        };
+        let Some(date) = date
+            .split_once(' ')
+            .and_then(|(date, time)| BoxTime::parse(date, time))
+        else {
+            continue;
+        };
+        let duration = duration
+            .split_once(':')
+            .and_then(|(h, m)| Some(h.parse::<u32>().ok()? * 60 + m.parse::<u32>().ok()?))
+            .unwrap_or_default();
+        calls.push(Call {
+            typ,
+            date,
+            name: name.to_string(),
+            number: number.to_string(),
+            extension: extension.to_string(),
+            duration,
+        });
+    }
+    calls
+}
+
+impl FritzClient<'_> {
+    fn calls(&self) -> AnyError<Vec<Call>> {
+        let res = self
+            .client
+            .get(format!("{base}{CALLS_PATH}", base = self.config.base_url))
+            .query(&[("sid", self.session.sid.as_str()), ("csv", "")])
+            .send()?
+            .error_for_status()?
+            .text()?;
+        Ok(parse_calls(&res))
+    }
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Args {
+    /// Only show calls since a date, `YYYY-MM-DD [HH:MM]`.
+    #[arg(long, value_parser = parse_since)]
+    since: Option<BoxTime>,
+    /// Only show calls of this type.
+    #[arg(long = "type", value_enum)]
+    typ: Option<CallType>,
+    /// How to render the calls.
+    #[arg(short, long, value_enum, default_value_t)]
+    output: table::Format,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct CallRow {
+    #[serde(rename = "TYPE")]
+    typ: CallType,
+    date: String,
+    name: String,
+    number: String,
+    extension: String,
+    duration: String,
+}
+
+impl<'a> table::TableRow<'a> for CallRow {}
+
+pub(super) fn run(api: &FritzClient, args: &Args) -> AnyError<()> {
+    let rows: Vec<_> = api
+        .calls()?
+        .into_iter()
+        .filter(|call| args.since.is_none_or(|since| call.date >= since))
+        .filter(|call| args.typ.is_none_or(|typ| call.typ == typ))
+        .map(|call| CallRow {
+            typ: call.typ,
+            date: call.date.to_string(),
+            name: call.name,
+            number: call.number,
+            extension: call.extension,
+            duration: format!("{}:{:02}", call.duration / 60, call.duration % 60),
+        })
+        .collect();
+    println!("{}", table::Renderer::default().render(args.output, &rows));
+    Ok(())
+}
@@ -0,0 +1,183 @@
+use super::{AnyError, FritzClient};
+use std::{
+    env, fs,
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+};
+
+const WAN_SERVICE: &str = "WANIPConnection:1";
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Protocol {
+    /// The DynDNS2 protocol most providers speak, `--url` is the update endpoint.
+    Dyndns2,
+    /// A GET of `--url`.
+    Get,
+    /// A POST of `--body` to `--url`.
+    Post,
+}
+
+/// The addresses last pushed, `ipv4` and `ipv6` tab separated.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Addresses {
+    ipv4: String,
+    ipv6: String,
+}
+
+impl Addresses {
+    fn query(api: &FritzClient) -> AnyError<Self> {
+        let client = api.tr064()?;
+        let ipv4 = client.external_ip()?;
+        // Offline or reconnecting boxes report `0.0.0.0` or nothing at all.
+        if !ipv4
+            .parse::<Ipv4Addr>()
+            .is_ok_and(|ip| !ip.is_unspecified())
+        {
+            return Err("The box has no external IP right now, nothing to push.".into());
+        }
+        // Without IPv6 the action fails, which is fine.
+        let ipv6 = client
+            .call(WAN_SERVICE, "X_AVM-DE_GetExternalIPv6Address", &[])
+            .ok()
+            .and_then(|out| out.get("NewExternalIPv6Address").map(String::from))
+            .unwrap_or_default();
+        Ok(Self { ipv4, ipv6 })
+    }
+
+    fn load(path: &Path) -> Option<Self> {
+        let state = fs::read_to_string(path).ok()?;
+        // Only the newline, without IPv6 the line ends in the tab.
+        let (ipv4, ipv6) = state.trim_end_matches('\n').split_once('\t')?;
+        Some(Self {
+            ipv4: ipv4.into(),
+            ipv6: ipv6.into(),
+        })
+    }
+
+    fn save(&self, path: &Path) -> AnyError<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, format!("{}\t{}\n", self.ipv4, self.ipv6))?;
+        Ok(())
+    }
+
+    /// Fill in the `{ip}` and `{ip6}` placeholders.
+    fn fill(&self, template: &str) -> String {
+        template
+            .replace("{ip6}", &self.ipv6)
+            .replace("{ip}", &self.ipv4)
+    }
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Args {
+    /// The update URL, may contain `{ip}` and `{ip6}` placeholders.
+    #[arg(long)]
+    url: String,
+    #[arg(long, value_enum, default_value_t = Protocol::Dyndns2)]
+    protocol: Protocol,
+    /// The hostname to update, required for `dyndns2`.
+    #[arg(long)]
+    hostname: Option<String>,
+    /// Sent as basic auth.
+    #[arg(long)]
+    username: Option<String>,
+    /// Sent as basic auth.
+    #[arg(long)]
+    password: Option<String>,
+    /// The body for `post`, may contain placeholders too.
+    #[arg(long, default_value = "")]
+    body: String,
+    /// Where to keep the last pushed addresses, defaults to
+    /// `$XDG_STATE_HOME/bins/ddns`.
+    #[arg(long)]
+    state: Option<PathBuf>,
+    /// Push even if the addresses did not change.
+    #[arg(long)]
+    force: bool,
+}
+
+impl Args {
+    fn state_path(&self) -> AnyError<PathBuf> {
+        if let Some(state) = &self.state {
+            return Ok(state.clone());
+        }
+        let dir = match (env::var_os("XDG_STATE_HOME"), env::var_os("HOME")) {
+            (Some(state), _) => PathBuf::from(state),
+            (None, Some(home)) => PathBuf::from(home).join(".local/state"),
+            (None, None) => return Err("Cannot tell where to keep the state, pass --state!".into()),
+        };
+        Ok(dir.join("bins").join("ddns"))
+    }
+}
+
+/// Push the addresses, which only counts as done if the provider says so.
+fn push(api: &FritzClient, args: &Args, addresses: &Addresses) -> AnyError<()> {
+    let url = addresses.fill(&args.url);
+    let mut req = match args.protocol {
+        Protocol::Dyndns2 => {
+            let hostname = args
+                .hostname
+                .as_deref()
+                .ok_or("DynDNS2 needs a --hostname!")?;
+            let myip = match addresses.ipv6.is_empty() {
+                true => addresses.ipv4.clone(),
+                false => format!("{},{}", addresses.ipv4, addresses.ipv6),
+            };
+            api.client
+                .get(url)
+                .query(&[("hostname", hostname), ("myip", myip.as_str())])
+        }
+        Protocol::Get => api.client.get(url),
+        Protocol::Post => api.client.post(url).body(addresses.fill(&args.body)),
+    };
+    if let Some(username) = &args.username {
+        req = req.basic_auth(username, args.password.as_ref());
+    }
+    let res = req.send()?.error_for_status()?.text()?;
+    if let Protocol::Dyndns2 = args.protocol {
+        // Anything but `good` and `nochg` is an error like `badauth` or `nohost`.
+        let code = res.split_whitespace().next().unwrap_or_default();
+        if !matches!(code, "good" | "nochg") {
+            return Err(format!("The update was refused: {}", res.trim()).into());
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn run(api: &FritzClient, args: &Args) -> AnyError<()> {
+    let state = args.state_path()?;
+    let addresses = Addresses::query(api)?;
+    let last = Addresses::load(&state);
+    if !args.force && last.as_ref() == Some(&addresses) {
+        println!("Still on {}, nothing to push.", addresses.ipv4);
+        return Ok(());
+    }
+    push(api, args, &addresses)?;
+    addresses.save(&state)?;
+    match last {
+        Some(last) => println!("Pushed {} (was {}).", addresses.ipv4, last.ipv4),
+        None => println!("Pushed {}.", addresses.ipv4),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn addresses_survive_save_and_load() {
+        let path = env::temp_dir().join(format!("bins-ddns-test-{}", std::process::id()));
+        for ipv6 in ["", "2001:db8::1"] {
+            let addresses = Addresses {
+                ipv4: "203.0.113.7".into(),
+                ipv6: ipv6.into(),
+            };
+            addresses.save(&path).unwrap();
+            assert_eq!(Addresses::load(&path), Some(addresses));
+        }
+        fs::remove_file(path).unwrap();
+    }
+}
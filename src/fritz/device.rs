@@ -0,0 +1,226 @@
+use super::{AnyError, Device, FritzClient};
+use crate::table;
+use serde::Serialize;
+use std::net::{Ipv4Addr, UdpSocket};
+
+const HOSTS_SERVICE: &str = "Hosts:1";
+const WOL_PORT: u16 = 9;
+
+impl FritzClient<'_> {
+    /// Find a known device by its name, IP, MAC or UID.
+    pub(super) fn device(&self, query: &str) -> AnyError<Device> {
+        // Quietly, the JSON and CSV output must stay parseable.
+        self.poll_devices()?
+            .find(query)
+            .ok_or_else(|| format!("No device named {query}!").into())
+    }
+}
+
+fn mac(device: &Device) -> AnyError<&str> {
+    device
+        .mac
+        .as_deref()
+        .ok_or_else(|| format!("Device {} has no MAC address!", device.uid).into())
+}
+
+/// Six times `0xff`, then the MAC sixteen times.
+fn magic_packet(mac: &str) -> AnyError<Vec<u8>> {
+    let bytes = mac
+        .split([':', '-'])
+        .map(|byte| u8::from_str_radix(byte, 16))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("Invalid MAC address {mac}!"))?;
+    if bytes.len() != 6 {
+        return Err(format!("Invalid MAC address {mac}!").into());
+    }
+    let mut packet = vec![0xff; 6];
+    for _ in 0..16 {
+        packet.extend(&bytes);
+    }
+    Ok(packet)
+}
+
+/// Broadcasts only reach our own LAN, which is just where the device has to be.
+fn send_magic_packet(mac: &str) -> AnyError<()> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_broadcast(true)?;
+    socket.send_to(&magic_packet(mac)?, (Ipv4Addr::BROADCAST, WOL_PORT))?;
+    Ok(())
+}
+
+#[derive(clap::Parser)]
+struct RenameArgs {
+    /// The name, IP, MAC or UID of the device.
+    device: String,
+    /// The new name.
+    name: String,
+}
+
+#[derive(clap::Subcommand)]
+enum Commands {
+    /// Rename a device.
+    Rename(RenameArgs),
+}
+
+#[derive(clap::Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub(super) struct Cli {
+    #[command(subcommand)]
+    command: Option<Commands>,
+    /// The name, IP, MAC or UID of the device.
+    #[arg(required = true)]
+    device: Option<String>,
+    /// How to render the details.
+    #[arg(short, long, value_enum, default_value_t)]
+    output: table::Format,
+}
+
+#[derive(clap::Parser)]
+pub(super) struct WolArgs {
+    /// The name, IP, MAC or UID of the device.
+    device: String,
+    /// Send the magic packet from this machine instead of the box.
+    #[arg(long)]
+    local: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct DetailRow {
+    field: String,
+    value: String,
+}
+
+impl<'a> table::TableRow<'a> for DetailRow {}
+
+impl Cli {
+    fn details(&self, api: &FritzClient, query: &str) -> AnyError<()> {
+        let device = api.device(query)?;
+        let mut details = vec![
+            ("name", device.name.clone().unwrap_or_default()),
+            ("uid", device.uid.clone()),
+            ("mac", device.mac.clone().unwrap_or_default()),
+            (
+                "ip",
+                device
+                    .ipv4
+                    .as_ref()
+                    .map(|ipv4| ipv4.ip.clone())
+                    .unwrap_or_default(),
+            ),
+            ("state", device.state.clone().unwrap_or_default()),
+            ("connection", device.typ.clone().unwrap_or_default()),
+            ("model", device.model.clone().unwrap_or_default()),
+            ("classes", device.classes.clone().unwrap_or_default()),
+            (
+                "trusted",
+                (if device.is_trusted == Some(true) {
+                    "Yes"
+                } else {
+                    "No"
+                })
+                .into(),
+            ),
+            (
+                "last used",
+                device
+                    .ipv4
+                    .as_ref()
+                    .and_then(|ipv4| ipv4.lastused.clone())
+                    .unwrap_or_default(),
+            ),
+        ];
+        // The web UI leaves out what the box knows about the lease. That takes
+        // TR-064, without it the fields stay empty.
+        let entry = device.mac.as_ref().and_then(|mac| {
+            api.tr064()
+                .and_then(|client| {
+                    client.call(
+                        HOSTS_SERVICE,
+                        "GetSpecificHostEntry",
+                        &[("NewMACAddress", mac.as_str())],
+                    )
+                })
+                .ok()
+        });
+        for (field, name) in [
+            ("interface", "NewInterfaceType"),
+            ("address source", "NewAddressSource"),
+            ("lease remaining", "NewLeaseTimeRemaining"),
+        ] {
+            let value = entry.as_ref().and_then(|entry| entry.get(name));
+            details.push((field, value.unwrap_or_default().into()));
+        }
+        match self.output {
+            table::Format::Json => {
+                let object: serde_json::Map<_, _> = details
+                    .into_iter()
+                    .map(|(field, value)| (field.into(), serde_json::Value::String(value)))
+                    .collect();
+                println!("{}", serde_json::to_string(&object)?);
+            }
+            format => {
+                let rows: Vec<_> = details
+                    .into_iter()
+                    .map(|(field, value)| DetailRow {
+                        field: field.into(),
+                        value,
+                    })
+                    .collect();
+                println!("{}", table::Renderer::default().render(format, &rows));
+            }
+        }
+        Ok(())
+    }
+
+    fn rename(&self, api: &FritzClient, args: &RenameArgs) -> AnyError<()> {
+        let device = api.device(&args.device)?;
+        api.tr064()?.call(
+            HOSTS_SERVICE,
+            "X_AVM-DE_SetHostNameByMACAddress",
+            &[
+                ("NewMACAddress", mac(&device)?),
+                ("NewHostName", args.name.as_str()),
+            ],
+        )?;
+        println!(
+            "Renamed {} to {}.",
+            device.name.as_deref().unwrap_or(&device.uid),
+            args.name
+        );
+        Ok(())
+    }
+
+    pub(super) fn run(&self, api: &FritzClient) -> AnyError<()> {
+        match (&self.command, &self.device) {
+            (Some(Commands::Rename(args)), _) => self.rename(api, args),
+            (None, Some(query)) => self.details(api, query),
+            (None, None) => Err("Missing device!".into()),
+        }
+    }
+}
+
+pub(super) fn wol(api: &FritzClient, args: &WolArgs) -> AnyError<()> {
+    let device = api.device(&args.device)?;
+    let mac = mac(&device)?;
+    let name = device.name.as_deref().unwrap_or(mac);
+    if !args.local {
+        let woken = api.tr064().and_then(|client| {
+            client.call(
+                HOSTS_SERVICE,
+                "X_AVM-DE_WakeOnLANByMACAddress",
+                &[("NewMACAddress", mac)],
+            )
+        });
+        match woken {
+            Ok(_) => {
+                println!("Woke {name} through the box.");
+                return Ok(());
+            }
+            Err(err) => eprintln!("The box could not wake {name}: {err}"),
+        }
+    }
+    send_magic_packet(mac)?;
+    println!("Sent a magic packet for {name} from this machine.");
+    Ok(())
+}
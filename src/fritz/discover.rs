@@ -0,0 +1,146 @@
+use super::{AnyError, POLL_INTERVAL};
+use crate::table;
+use reqwest as rw;
+use serde::{Deserialize, Serialize};
+use std::{
+    net::UdpSocket,
+    time::{Duration, Instant},
+};
+
+const SSDP_ADDRESS: &str = "239.255.255.250:1900";
+const SEARCH_TARGET: &str = "urn:dslforum-org:device:InternetGatewayDevice:1";
+/// Where the box usually is, when the local DNS of the box is used.
+const DEFAULT_URL: &str = "http://fritz.box";
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Deserialize)]
+struct Description {
+    #[serde(rename = "systemVersion")]
+    system_version: Option<SystemVersion>,
+    device: DeviceDescription,
+}
+
+#[derive(Debug, Deserialize)]
+struct SystemVersion {
+    #[serde(rename = "Display")]
+    display: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceDescription {
+    #[serde(rename = "friendlyName")]
+    friendly_name: Option<String>,
+    #[serde(rename = "modelName")]
+    model_name: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct BoxRow {
+    model: String,
+    name: String,
+    firmware: String,
+    url: String,
+}
+
+impl<'a> table::TableRow<'a> for BoxRow {}
+
+/// The description URLs of the boxes answering an SSDP search within the timeout.
+fn search(timeout: Duration) -> AnyError<Vec<String>> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    let request = format!(
+        "M-SEARCH * HTTP/1.1\r\n\
+         HOST: {SSDP_ADDRESS}\r\n\
+         MAN: \"ssdp:discover\"\r\n\
+         MX: {mx}\r\n\
+         ST: {SEARCH_TARGET}\r\n\r\n",
+        mx = timeout.as_secs().max(1),
+    );
+    socket.send_to(request.as_bytes(), SSDP_ADDRESS)?;
+    let deadline = Instant::now() + timeout;
+    let mut locations = vec![];
+    let mut buf = [0; 2048];
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        socket.set_read_timeout(Some(left.max(Duration::from_millis(1))))?;
+        let Ok((len, _)) = socket.recv_from(&mut buf) else {
+            break;
+        };
+        let location = String::from_utf8_lossy(&buf[..len])
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("location"))
+            .map(|(_, value)| value.trim().to_string());
+        if let Some(location) = location.filter(|l| !locations.contains(l)) {
+            locations.push(location);
+        }
+    }
+    Ok(locations)
+}
+
+/// Describe a box by its description, its web UI is on the same host.
+fn describe(client: &rw::blocking::Client, location: &str) -> AnyError<BoxRow> {
+    let raw = client.get(location).send()?.error_for_status()?.text()?;
+    let description: Description = serde_xml_rs::from_str(&raw)?;
+    let mut url = rw::Url::parse(location)?;
+    url.set_port(None)
+        .map_err(|_| format!("{location} is not a valid url"))?;
+    url.set_path("");
+    Ok(BoxRow {
+        model: description.device.model_name.unwrap_or_default(),
+        name: description.device.friendly_name.unwrap_or_default(),
+        firmware: description
+            .system_version
+            .and_then(|version| version.display)
+            .unwrap_or_default(),
+        url: url.as_str().trim_end_matches('/').to_string(),
+    })
+}
+
+fn discover(timeout: Duration) -> AnyError<Vec<BoxRow>> {
+    let client = rw::blocking::Client::new();
+    let mut rows = vec![];
+    for location in search(timeout)? {
+        match describe(&client, &location) {
+            Ok(row) => rows.push(row),
+            Err(err) => eprintln!("Could not describe {location}: {err}"),
+        }
+    }
+    Ok(rows)
+}
+
+/// The box to use without `FRITZ_URL`: `http://fritz.box` if it answers, else
+/// the first box found on the network.
+pub(super) fn default_url() -> AnyError<String> {
+    let reachable = rw::blocking::Client::new()
+        .get(format!("{DEFAULT_URL}/login_sid.lua"))
+        .timeout(POLL_INTERVAL)
+        .send()
+        .is_ok_and(|res| res.status().is_success());
+    if reachable {
+        return Ok(DEFAULT_URL.into());
+    }
+    discover(DEFAULT_TIMEOUT)?
+        .into_iter()
+        .next()
+        .map(|row| row.url)
+        .ok_or_else(|| "expected FRITZ_URL env var, no box found on the network".into())
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Args {
+    /// How long to wait for the boxes to answer.
+    #[arg(long, default_value = "3s")]
+    timeout: humantime::Duration,
+    /// How to render the boxes.
+    #[arg(short, long, value_enum, default_value_t)]
+    output: table::Format,
+}
+
+pub(super) fn run(args: &Args) -> AnyError<()> {
+    let rows = discover(Duration::from(args.timeout))?;
+    if rows.is_empty() {
+        return Err("No box found on the network!".into());
+    }
+    println!("{}", table::Renderer::default().render(args.output, &rows));
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use super::{tr064::Tr064Client, AnyError, FritzClient};
+use crate::table;
+use serde::Serialize;
+
+const DSL_SERVICE: &str = "WANDSLInterfaceConfig:1";
+
+/// The line state of one direction.
+#[derive(Debug, Default, Serialize)]
+pub(super) struct DslDirection {
+    /// In kbit/s.
+    pub(super) sync_rate: u64,
+    /// In kbit/s.
+    pub(super) attainable_rate: u64,
+    /// In dB.
+    pub(super) snr_margin: f64,
+    /// In dB.
+    pub(super) attenuation: f64,
+    pub(super) crc_errors: u64,
+    pub(super) fec_errors: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub(super) struct DslLine {
+    pub(super) status: String,
+    /// The modulation, e.g. `VDSL`.
+    pub(super) modulation: String,
+    /// The standard in use, e.g. `G.993.2`.
+    pub(super) standard: String,
+    /// `Fast` or `Interleaved`.
+    pub(super) data_path: String,
+    pub(super) downstream: DslDirection,
+    pub(super) upstream: DslDirection,
+    pub(super) errored_seconds: u64,
+    pub(super) severely_errored_seconds: u64,
+    /// How often the line lost its sync since the box started.
+    pub(super) resyncs: u64,
+}
+
+/// The box counts margin and attenuation in tenths of a dB.
+fn decibel(tenths: Option<i64>) -> f64 {
+    tenths.unwrap_or_default() as f64 / 10.0
+}
+
+impl DslLine {
+    pub(super) fn query(client: &Tr064Client) -> AnyError<Self> {
+        let info = client.call(DSL_SERVICE, "GetInfo", &[])?;
+        // Errors on our end are downstream errors, the ATUC ones are seen by
+        // the exchange and so upstream errors.
+        let stats = client.call(DSL_SERVICE, "GetStatisticsTotal", &[])?;
+        let count = |name: &str| stats.parse(name).unwrap_or_default();
+        Ok(Self {
+            status: info.get("NewStatus").unwrap_or_default().into(),
+            modulation: info.get("NewModulationType").unwrap_or_default().into(),
+            standard: info.get("NewStandardUsed").unwrap_or_default().into(),
+            data_path: info.get("NewDataPath").unwrap_or_default().into(),
+            downstream: DslDirection {
+                sync_rate: info.parse("NewDownstreamCurrRate").unwrap_or_default(),
+                attainable_rate: info.parse("NewDownstreamMaxRate").unwrap_or_default(),
+                snr_margin: decibel(info.parse("NewDownstreamNoiseMargin")),
+                attenuation: decibel(info.parse("NewDownstreamAttenuation")),
+                crc_errors: count("NewCRCErrors"),
+                fec_errors: count("NewFECErrors"),
+            },
+            upstream: DslDirection {
+                sync_rate: info.parse("NewUpstreamCurrRate").unwrap_or_default(),
+                attainable_rate: info.parse("NewUpstreamMaxRate").unwrap_or_default(),
+                snr_margin: decibel(info.parse("NewUpstreamNoiseMargin")),
+                attenuation: decibel(info.parse("NewUpstreamAttenuation")),
+                crc_errors: count("NewATUCCRCErrors"),
+                fec_errors: count("NewATUCFECErrors"),
+            },
+            errored_seconds: count("NewErroredSecs"),
+            severely_errored_seconds: count("NewSeverelyErroredSecs"),
+            resyncs: count("NewLinkRetrain"),
+        })
+    }
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Args {
+    /// How to render the line statistics.
+    #[arg(short, long, value_enum, default_value_t)]
+    output: table::Format,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct DslRow {
+    metric: String,
+    downstream: String,
+    upstream: String,
+}
+
+impl<'a> table::TableRow<'a> for DslRow {}
+
+impl DslRow {
+    fn new(metric: &str, downstream: String, upstream: String) -> Self {
+        Self {
+            metric: metric.into(),
+            downstream,
+            upstream,
+        }
+    }
+
+    /// A value of the whole line, shown once.
+    fn line(metric: &str, value: String) -> Self {
+        Self::new(metric, value, String::new())
+    }
+}
+
+pub(super) fn run(api: &FritzClient, args: &Args) -> AnyError<()> {
+    let line = DslLine::query(&api.tr064()?)?;
+    // The typed line is more useful to scripts than the rows.
+    if let table::Format::Json = args.output {
+        println!("{}", serde_json::to_string(&line)?);
+        return Ok(());
+    }
+    let (down, up) = (&line.downstream, &line.upstream);
+    let rows = [
+        DslRow::line("status", line.status.clone()),
+        DslRow::line(
+            "profile",
+            format!("{} {} ({})", line.modulation, line.standard, line.data_path),
+        ),
+        DslRow::new(
+            "sync rate",
+            format!("{} kbit/s", down.sync_rate),
+            format!("{} kbit/s", up.sync_rate),
+        ),
+        DslRow::new(
+            "attainable rate",
+            format!("{} kbit/s", down.attainable_rate),
+            format!("{} kbit/s", up.attainable_rate),
+        ),
+        DslRow::new(
+            "snr margin",
+            format!("{:.1} dB", down.snr_margin),
+            format!("{:.1} dB", up.snr_margin),
+        ),
+        DslRow::new(
+            "attenuation",
+            format!("{:.1} dB", down.attenuation),
+            format!("{:.1} dB", up.attenuation),
+        ),
+        DslRow::new(
+            "crc errors",
+            down.crc_errors.to_string(),
+            up.crc_errors.to_string(),
+        ),
+        DslRow::new(
+            "fec errors",
+            down.fec_errors.to_string(),
+            up.fec_errors.to_string(),
+        ),
+        DslRow::line("errored seconds", line.errored_seconds.to_string()),
+        DslRow::line(
+            "severely errored seconds",
+            line.severely_errored_seconds.to_string(),
+        ),
+        DslRow::line("resyncs", line.resyncs.to_string()),
+    ];
+    println!("{}", table::Renderer::default().render(args.output, &rows));
+    Ok(())
+}
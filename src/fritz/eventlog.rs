@@ -0,0 +1,189 @@
+use super::{parse_interval, parse_since, AnyError, BoxTime, FritzClient};
+use crate::table;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, thread, time::Duration};
+
+const LOG_COLUMN_WIDTHS: [usize; 4] = [10, 8, 9, 0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+enum Category {
+    Internet,
+    Wlan,
+    Telephony,
+    System,
+}
+
+impl Category {
+    const ALL: [Self; 4] = [Self::Internet, Self::Wlan, Self::Telephony, Self::System];
+
+    /// The filter of the log page, the box does not tag entries itself.
+    fn filter(&self) -> &'static str {
+        match self {
+            Self::System => "1",
+            Self::Internet => "2",
+            Self::Telephony => "3",
+            Self::Wlan => "4",
+        }
+    }
+}
+
+/// Older firmware sends entries as `[date, time, message, ...]` arrays.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawEntry {
+    Object {
+        date: String,
+        time: String,
+        msg: String,
+    },
+    Array(Vec<serde_json::Value>),
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct LogData {
+    #[serde(default)]
+    log: Vec<RawEntry>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Log {
+    data: LogData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Entry {
+    time: BoxTime,
+    category: Category,
+    message: String,
+}
+
+impl Entry {
+    fn parse(raw: RawEntry, category: Category) -> Option<Self> {
+        let (date, time, message) = match raw {
+            RawEntry::Object { date, time, msg } => (date, time, msg),
+            RawEntry::Array(values) => {
+                let field = |i: usize| values.get(i)?.as_str().map(String::from);
+                (field(0)?, field(1)?, field(2)?)
+            }
+        };
+        Some(Self {
+            time: BoxTime::parse(&date, &time)?,
+            category,
+            message,
+        })
+    }
+}
+
+impl FritzClient<'_> {
+    /// Query the event log of a category, newest first.
+    fn event_log(&self, category: Category) -> AnyError<Vec<Entry>> {
+        let res = self.data_lua("log", &[("xhrId", "all"), ("filter", category.filter())])?;
+        let log: Log = serde_json::from_str(&res)?;
+        Ok(log
+            .data
+            .log
+            .into_iter()
+            .filter_map(|raw| Entry::parse(raw, category))
+            .collect())
+    }
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Args {
+    /// Only show entries of this category.
+    #[arg(long, value_enum)]
+    category: Option<Category>,
+    /// Only show entries since a date, `YYYY-MM-DD [HH:MM]`.
+    #[arg(long, value_parser = parse_since)]
+    since: Option<BoxTime>,
+    /// Only show entries containing this text, ignoring case.
+    #[arg(long)]
+    grep: Option<String>,
+    /// Keep polling and print new entries as they come in.
+    #[arg(short, long)]
+    follow: bool,
+    /// How often to poll with `--follow`.
+    #[arg(long, default_value = "10s", value_parser = parse_interval)]
+    interval: humantime::Duration,
+    /// How to render the entries.
+    #[arg(short, long, value_enum, default_value_t)]
+    output: table::Format,
+}
+
+impl Args {
+    fn matches(&self, entry: &Entry) -> bool {
+        self.since.is_none_or(|since| entry.time >= since)
+            && self
+                .grep
+                .as_ref()
+                .is_none_or(|grep| entry.message.to_lowercase().contains(&grep.to_lowercase()))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct LogRow {
+    date: String,
+    time: String,
+    category: Category,
+    message: String,
+}
+
+impl<'a> table::TableRow<'a> for LogRow {}
+
+impl From<&Entry> for LogRow {
+    fn from(entry: &Entry) -> Self {
+        let time = entry.time;
+        Self {
+            date: format!("{:04}-{:02}-{:02}", time.year, time.month, time.day),
+            time: format!("{:02}:{:02}:{:02}", time.hour, time.minute, time.second),
+            category: entry.category,
+            message: entry.message.clone(),
+        }
+    }
+}
+
+/// The entries of all wanted categories, oldest first.
+fn entries(api: &FritzClient, args: &Args) -> AnyError<Vec<Entry>> {
+    let categories = match args.category {
+        Some(category) => vec![category],
+        None => Category::ALL.to_vec(),
+    };
+    let mut entries = vec![];
+    for category in categories {
+        entries.extend(api.event_log(category)?);
+    }
+    entries.retain(|entry| args.matches(entry));
+    entries.sort_by_key(|entry| entry.time);
+    Ok(entries)
+}
+
+fn follow(api: &FritzClient, args: &Args) -> AnyError<()> {
+    let mut renderer = table::LiveRenderer::new(LOG_COLUMN_WIDTHS.to_vec());
+    // Entries only have seconds, so remember all of them instead of the last time.
+    let mut seen = HashSet::new();
+    loop {
+        for entry in entries(api, args)? {
+            if !seen.insert(entry.clone()) {
+                continue;
+            }
+            let row = LogRow::from(&entry);
+            match args.output {
+                table::Format::Table => println!("{}", renderer.render_row(&row)),
+                table::Format::Json => println!("{}", serde_json::to_string(&row)?),
+                table::Format::Csv => println!("{}", renderer.render_csv_row(&row)),
+            }
+        }
+        thread::sleep(Duration::from(args.interval));
+    }
+}
+
+pub(super) fn run(api: &FritzClient, args: &Args) -> AnyError<()> {
+    if args.follow {
+        return follow(api, args);
+    }
+    let rows: Vec<_> = entries(api, args)?.iter().map(LogRow::from).collect();
+    println!("{}", table::Renderer::default().render(args.output, &rows));
+    Ok(())
+}
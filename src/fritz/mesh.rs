@@ -0,0 +1,245 @@
+use super::{tr064::Tr064Client, AnyError, FritzClient};
+use reqwest as rw;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The mesh roles of the boxes, every other device is `unknown`.
+const MASTER: &str = "master";
+const REPEATER: &str = "slave";
+const CONNECTED: &str = "CONNECTED";
+
+/// The mesh as the box draws it on its mesh overview page.
+#[derive(Debug, Default, Deserialize)]
+pub(super) struct Topology {
+    #[serde(default)]
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Node {
+    uid: String,
+    #[serde(default)]
+    device_name: String,
+    #[serde(default)]
+    device_model: String,
+    #[serde(default)]
+    device_mac_address: String,
+    #[serde(default)]
+    mesh_role: String,
+    #[serde(default)]
+    node_interfaces: Vec<Interface>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Interface {
+    #[serde(default)]
+    mac_address: String,
+    #[serde(default)]
+    node_links: Vec<Link>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Link {
+    #[serde(rename = "type", default)]
+    typ: String,
+    #[serde(default)]
+    state: String,
+    node_1_uid: String,
+    node_2_uid: String,
+    /// In kbit/s.
+    #[serde(default)]
+    cur_data_rate_rx: u64,
+    #[serde(default)]
+    cur_data_rate_tx: u64,
+}
+
+impl Node {
+    fn is_access_point(&self) -> bool {
+        matches!(self.mesh_role.as_str(), MASTER | REPEATER)
+    }
+
+    fn has_mac(&self, mac: &str) -> bool {
+        self.device_mac_address.eq_ignore_ascii_case(mac)
+            || self
+                .node_interfaces
+                .iter()
+                .any(|interface| interface.mac_address.eq_ignore_ascii_case(mac))
+    }
+
+    fn label(&self) -> String {
+        match self.device_model.as_str() {
+            "" => self.device_name.clone(),
+            model => format!("{} ({model})", self.device_name),
+        }
+    }
+}
+
+impl Topology {
+    fn root(&self) -> Option<&Node> {
+        self.nodes
+            .iter()
+            .find(|node| node.mesh_role == MASTER)
+            .or(self.nodes.first())
+    }
+
+    /// The connected nodes below each node, walked breadth first from the master
+    /// so every node shows up once.
+    fn children(&self) -> HashMap<&str, Vec<(&Node, &Link)>> {
+        let nodes: HashMap<_, _> = self.nodes.iter().map(|n| (n.uid.as_str(), n)).collect();
+        let mut children: HashMap<&str, Vec<(&Node, &Link)>> = HashMap::new();
+        let Some(root) = self.root() else {
+            return children;
+        };
+        let mut seen = HashSet::from([root.uid.as_str()]);
+        let mut queue = VecDeque::from([root]);
+        while let Some(node) = queue.pop_front() {
+            let links = node
+                .node_interfaces
+                .iter()
+                .flat_map(|interface| &interface.node_links)
+                .filter(|link| link.state == CONNECTED);
+            for link in links {
+                let other = match link.node_1_uid == node.uid {
+                    true => &link.node_2_uid,
+                    false => &link.node_1_uid,
+                };
+                let Some(child) = nodes.get(other.as_str()) else {
+                    continue;
+                };
+                if seen.insert(child.uid.as_str()) {
+                    children
+                        .entry(node.uid.as_str())
+                        .or_default()
+                        .push((child, link));
+                    queue.push_back(child);
+                }
+            }
+        }
+        children
+    }
+
+    /// The names of the access points the devices are attached to, by the MACs
+    /// of the devices in upper case.
+    pub(super) fn access_points(&self) -> HashMap<String, String> {
+        let nodes: HashMap<_, _> = self.nodes.iter().map(|n| (n.uid.as_str(), n)).collect();
+        let mut parents = HashMap::new();
+        for (parent, children) in self.children() {
+            for (child, _) in children {
+                parents.insert(child.uid.as_str(), parent);
+            }
+        }
+        let mut access_points = HashMap::new();
+        for device in &self.nodes {
+            let mut node = device;
+            // Devices behind a switch hang off another device first.
+            let access_point = loop {
+                match parents
+                    .get(node.uid.as_str())
+                    .and_then(|uid| nodes.get(uid))
+                {
+                    Some(parent) if parent.is_access_point() => break Some(*parent),
+                    Some(parent) => node = parent,
+                    None => break None,
+                }
+            };
+            let Some(access_point) = access_point else {
+                continue;
+            };
+            let macs = device
+                .node_interfaces
+                .iter()
+                .map(|interface| &interface.mac_address)
+                .chain([&device.device_mac_address])
+                .filter(|mac| !mac.is_empty());
+            for mac in macs {
+                access_points.insert(mac.to_uppercase(), access_point.device_name.clone());
+            }
+        }
+        access_points
+    }
+
+    fn repeaters(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter().filter(|node| node.mesh_role == REPEATER)
+    }
+
+    fn write_tree(
+        &self,
+        children: &HashMap<&str, Vec<(&Node, &Link)>>,
+        node: &Node,
+        prefix: &str,
+        out: &mut String,
+    ) {
+        let Some(nodes) = children.get(node.uid.as_str()) else {
+            return;
+        };
+        for (i, (child, link)) in nodes.iter().enumerate() {
+            let last = i == nodes.len() - 1;
+            let mut line = format!(
+                "{} {} {}",
+                if last { "└──" } else { "├──" },
+                child.label(),
+                link.typ
+            );
+            if link.cur_data_rate_rx > 0 || link.cur_data_rate_tx > 0 {
+                line += &format!(
+                    " {}/{} Mbit/s",
+                    link.cur_data_rate_rx / 1000,
+                    link.cur_data_rate_tx / 1000
+                );
+            }
+            out.push_str(&format!("{prefix}{line}\n"));
+            let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+            self.write_tree(children, child, &prefix, out);
+        }
+    }
+
+    /// The connected nodes as a tree below the master.
+    fn tree(&self) -> String {
+        let Some(root) = self.root() else {
+            return String::new();
+        };
+        let mut out = format!("{}\n", root.label());
+        self.write_tree(&self.children(), root, "", &mut out);
+        out
+    }
+}
+
+impl Tr064Client {
+    /// Query the mesh topology, the box hands out a path to it like for the hosts.
+    pub(super) fn mesh(&self) -> AnyError<Topology> {
+        let out = self.call("Hosts:1", "X_AVM-DE_GetMeshListPath", &[])?;
+        let path = out
+            .get("NewX_AVM-DE_MeshListPath")
+            .ok_or("The box has no mesh list!")?;
+        let topology: Topology = serde_json::from_str(&self.fetch(path)?)?;
+        Ok(topology)
+    }
+}
+
+/// The URLs of the repeaters, with their IPs from the devices of the master
+/// and otherwise like the URL of the master.
+pub(super) fn repeater_urls(api: &FritzClient) -> AnyError<Vec<String>> {
+    let client = api.tr064()?;
+    let topology = client.mesh()?;
+    let devices: Vec<_> = client.devices()?.devices().into_iter().flatten().collect();
+    let mut urls = vec![];
+    for repeater in topology.repeaters() {
+        let ip = devices
+            .iter()
+            .filter(|device| device.mac.as_ref().is_some_and(|mac| repeater.has_mac(mac)))
+            .find_map(|device| device.ipv4.as_ref())
+            .ok_or_else(|| format!("No IP known for the repeater {}!", repeater.device_name))?;
+        let mut url = rw::Url::parse(api.config.base_url)?;
+        url.set_host(Some(&ip.ip))?;
+        urls.push(url.as_str().trim_end_matches('/').to_string());
+    }
+    Ok(urls)
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Args {}
+
+pub(super) fn run(api: &FritzClient, _args: &Args) -> AnyError<()> {
+    print!("{}", api.tr064()?.mesh()?.tree());
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use super::{
+    dsl::{DslDirection, DslLine},
+    tr064::Tr064Client,
+    AnyError, Env, FritzApiFunctions, FritzClient, INTERNET_ONLINE,
+};
+use crate::metrics::{Kind, Metrics};
+
+/// Collects the FritzBox metrics, keeping the session and the TR-064 client
+/// across scrapes.
+pub(crate) struct Exporter<'a> {
+    api: FritzClient<'a>,
+    tr064: Option<Tr064Client>,
+}
+
+impl<'a> Exporter<'a> {
+    pub(crate) fn new(env: &'a Env) -> Self {
+        Self {
+            api: FritzClient::new_with_config(env.config()),
+            tr064: None,
+        }
+    }
+
+    /// Run a web UI query, logging in again once if the session expired.
+    fn web<T>(&mut self, query: impl Fn(&FritzClient) -> AnyError<T>) -> AnyError<T> {
+        if self.api.session.is_default_sid() {
+            self.api.login()?;
+        }
+        match query(&self.api) {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                self.api.login()?;
+                query(&self.api)
+            }
+        }
+    }
+
+    /// Run a TR-064 query, discovering the services again after an error.
+    fn tr064<T>(&mut self, query: impl Fn(&Tr064Client) -> AnyError<T>) -> AnyError<T> {
+        let client = match self.tr064.take() {
+            Some(client) => client,
+            None => self.api.tr064()?,
+        };
+        let value = query(&client)?;
+        self.tr064 = Some(client);
+        Ok(value)
+    }
+
+    pub(crate) fn collect(&mut self, metrics: &mut Metrics) -> AnyError<()> {
+        let overview = self.web(|api| api.poll_overview())?;
+        metrics.single(
+            "fritz_internet_up",
+            Kind::Gauge,
+            "Whether the internet connection is up.",
+            f64::from(u8::from(overview.data.internet.state == INTERNET_ONLINE)),
+        );
+        metrics.single(
+            "fritz_update_available",
+            Kind::Gauge,
+            "Whether a firmware update is available.",
+            f64::from(u8::from(overview.data.os.is_update_available)),
+        );
+
+        let counters = self.web(|api| api.counters())?.data;
+        metrics.describe(
+            "fritz_traffic_bytes",
+            Kind::Gauge,
+            "Bytes transferred in a period, from the online monitor.",
+        );
+        for (period, counter) in [
+            ("today", counters.today),
+            ("yesterday", counters.yesterday),
+            ("this_week", counters.this_week),
+            ("this_month", counters.this_month),
+            ("last_month", counters.last_month),
+        ] {
+            let Some(counter) = counter else {
+                continue;
+            };
+            for (direction, bytes) in [("received", counter.received()), ("sent", counter.sent())] {
+                metrics.sample(
+                    "fritz_traffic_bytes",
+                    &[("period", period), ("direction", direction)],
+                    bytes as f64,
+                );
+            }
+        }
+
+        let devices = self.tr064(|client| client.devices())?;
+        let data = devices.data.unwrap_or_default();
+        metrics.describe("fritz_devices", Kind::Gauge, "Known devices by state.");
+        for (state, devices) in [("active", data.active), ("passive", data.passive)] {
+            let count = devices.map(|devices| devices.len()).unwrap_or_default();
+            metrics.sample("fritz_devices", &[("state", state)], count as f64);
+        }
+
+        // Cable and fiber boxes have no DSL line.
+        let Ok(line) = self.tr064(DslLine::query) else {
+            return Ok(());
+        };
+        metrics.single(
+            "fritz_dsl_up",
+            Kind::Gauge,
+            "Whether the DSL line is in sync.",
+            f64::from(u8::from(line.status == "Up")),
+        );
+        let mut per_direction = |name, kind, help, value: fn(&DslDirection) -> f64| {
+            metrics.describe(name, kind, help);
+            for (direction, stats) in [("down", &line.downstream), ("up", &line.upstream)] {
+                metrics.sample(name, &[("direction", direction)], value(stats));
+            }
+        };
+        per_direction(
+            "fritz_dsl_sync_rate_bps",
+            Kind::Gauge,
+            "The synced rate in bit/s.",
+            |d| d.sync_rate as f64 * 1000.0,
+        );
+        per_direction(
+            "fritz_dsl_attainable_rate_bps",
+            Kind::Gauge,
+            "The attainable rate in bit/s.",
+            |d| d.attainable_rate as f64 * 1000.0,
+        );
+        per_direction(
+            "fritz_dsl_snr_margin_db",
+            Kind::Gauge,
+            "The SNR margin in dB.",
+            |d| d.snr_margin,
+        );
+        per_direction(
+            "fritz_dsl_attenuation_db",
+            Kind::Gauge,
+            "The attenuation in dB.",
+            |d| d.attenuation,
+        );
+        per_direction(
+            "fritz_dsl_crc_errors_total",
+            Kind::Counter,
+            "CRC errors.",
+            |d| d.crc_errors as f64,
+        );
+        per_direction(
+            "fritz_dsl_fec_errors_total",
+            Kind::Counter,
+            "FEC errors.",
+            |d| d.fec_errors as f64,
+        );
+        metrics.single(
+            "fritz_dsl_resyncs_total",
+            Kind::Counter,
+            "How often the line lost its sync.",
+            line.resyncs as f64,
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+use super::{tr064::Tr064Client, AnyError, Device, FritzClient};
+use crate::table;
+use serde::Serialize;
+
+const SERVICE: &str = "WANIPConnection:1";
+
+#[derive(Debug, Clone)]
+struct PortMapping {
+    remote_host: String,
+    external_port: u16,
+    protocol: String,
+    internal_port: u16,
+    internal_client: String,
+    enabled: bool,
+    description: String,
+    lease_duration: String,
+}
+
+impl PortMapping {
+    fn add(&self, client: &Tr064Client) -> AnyError<()> {
+        let external_port = self.external_port.to_string();
+        let internal_port = self.internal_port.to_string();
+        client.call(
+            SERVICE,
+            "AddPortMapping",
+            &[
+                ("NewRemoteHost", self.remote_host.as_str()),
+                ("NewExternalPort", external_port.as_str()),
+                ("NewProtocol", self.protocol.as_str()),
+                ("NewInternalPort", internal_port.as_str()),
+                ("NewInternalClient", self.internal_client.as_str()),
+                ("NewEnabled", if self.enabled { "1" } else { "0" }),
+                ("NewPortMappingDescription", self.description.as_str()),
+                ("NewLeaseDuration", self.lease_duration.as_str()),
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, client: &Tr064Client) -> AnyError<()> {
+        let external_port = self.external_port.to_string();
+        client.call(
+            SERVICE,
+            "DeletePortMapping",
+            &[
+                ("NewRemoteHost", self.remote_host.as_str()),
+                ("NewExternalPort", external_port.as_str()),
+                ("NewProtocol", self.protocol.as_str()),
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+fn port_mappings(client: &Tr064Client) -> AnyError<Vec<PortMapping>> {
+    let count: u32 = client
+        .call(SERVICE, "GetPortMappingNumberOfEntries", &[])?
+        .parse("NewPortMappingNumberOfEntries")
+        .unwrap_or_default();
+    let mut mappings = vec![];
+    for index in 0..count {
+        let index = index.to_string();
+        let entry = client.call(
+            SERVICE,
+            "GetGenericPortMappingEntry",
+            &[("NewPortMappingIndex", index.as_str())],
+        )?;
+        mappings.push(PortMapping {
+            remote_host: entry.get("NewRemoteHost").unwrap_or_default().into(),
+            external_port: entry.parse("NewExternalPort").unwrap_or_default(),
+            protocol: entry.get("NewProtocol").unwrap_or_default().into(),
+            internal_port: entry.parse("NewInternalPort").unwrap_or_default(),
+            internal_client: entry.get("NewInternalClient").unwrap_or_default().into(),
+            enabled: entry.flag("NewEnabled"),
+            description: entry
+                .get("NewPortMappingDescription")
+                .unwrap_or_default()
+                .into(),
+            lease_duration: entry.get("NewLeaseDuration").unwrap_or("0").into(),
+        });
+    }
+    Ok(mappings)
+}
+
+/// Resolve a device and the IP port sharing for it is keyed by.
+fn device_ip(api: &FritzClient, query: &str) -> AnyError<(Device, String)> {
+    let device = api.device(query)?;
+    let ip = device
+        .ipv4
+        .as_ref()
+        .map(|ipv4| ipv4.ip.clone())
+        .ok_or_else(|| format!("Device {query} has no IPv4 address!"))?;
+    Ok((device, ip))
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Protocol {
+    Tcp,
+    Udp,
+}
+
+impl Protocol {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tcp => "TCP",
+            Self::Udp => "UDP",
+        }
+    }
+}
+
+#[derive(clap::Parser)]
+struct ListArgs {}
+
+#[derive(clap::Parser)]
+struct AddArgs {
+    /// The name, IP or MAC of the device.
+    device: String,
+    /// The port opened on the internet side.
+    port: u16,
+    /// The port on the device. Defaults to the external one.
+    #[arg(long)]
+    internal_port: Option<u16>,
+    #[arg(long, value_enum, default_value_t = Protocol::Tcp)]
+    protocol: Protocol,
+    /// Shown in the web UI next to the rule.
+    #[arg(long, default_value = "bins")]
+    description: String,
+}
+
+#[derive(clap::Parser)]
+struct RuleArgs {
+    /// The name, IP or MAC of the device.
+    device: String,
+    /// The port opened on the internet side.
+    port: u16,
+    #[arg(long, value_enum, default_value_t = Protocol::Tcp)]
+    protocol: Protocol,
+}
+
+#[derive(clap::Subcommand)]
+enum Commands {
+    /// List all port sharing rules.
+    List(ListArgs),
+    /// Open a port for a device.
+    Add(AddArgs),
+    /// Remove the rule for a port of a device.
+    Remove(RuleArgs),
+    /// Enable the rule for a port of a device.
+    Enable(RuleArgs),
+    /// Disable the rule for a port of a device, but keep it.
+    Disable(RuleArgs),
+}
+
+/// Port sharing over TR-064 needs "Allow changes of security settings" turned
+/// on for the user, otherwise only rules of the calling host can be edited.
+#[derive(clap::Parser)]
+pub(super) struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct PortMappingRow {
+    device: String,
+    ip: String,
+    external: String,
+    internal: String,
+    protocol: String,
+    enabled: String,
+    description: String,
+}
+
+impl<'a> table::TableRow<'a> for PortMappingRow {}
+
+impl Cli {
+    fn list(&self, api: &FritzClient, _args: &ListArgs) -> AnyError<()> {
+        let client = api.tr064()?;
+        let devices: Vec<_> = api
+            .poll_devices()?
+            .devices()
+            .into_iter()
+            .flatten()
+            .collect();
+        let mut rows = vec![];
+        for mapping in port_mappings(&client)? {
+            let device = devices.iter().find(|device| {
+                device
+                    .ipv4
+                    .as_ref()
+                    .is_some_and(|ipv4| ipv4.ip == mapping.internal_client)
+            });
+            rows.push(PortMappingRow {
+                device: device.and_then(|d| d.name.clone()).unwrap_or_default(),
+                ip: mapping.internal_client,
+                external: mapping.external_port.to_string(),
+                internal: mapping.internal_port.to_string(),
+                protocol: mapping.protocol,
+                enabled: (if mapping.enabled { "Yes" } else { "No" }).into(),
+                description: mapping.description,
+            });
+        }
+        println!("{}", table::Renderer::default().to_string(&rows));
+        Ok(())
+    }
+
+    fn add(&self, api: &FritzClient, args: &AddArgs) -> AnyError<()> {
+        let (device, ip) = device_ip(api, &args.device)?;
+        let client = api.tr064()?;
+        let mapping = PortMapping {
+            remote_host: String::new(),
+            external_port: args.port,
+            protocol: args.protocol.as_str().into(),
+            internal_port: args.internal_port.unwrap_or(args.port),
+            internal_client: ip,
+            enabled: true,
+            description: args.description.clone(),
+            lease_duration: "0".into(),
+        };
+        mapping.add(&client)?;
+        println!(
+            "Opened {}/{} for {}.",
+            mapping.external_port,
+            mapping.protocol,
+            device.name.unwrap_or(mapping.internal_client)
+        );
+        Ok(())
+    }
+
+    /// Find the rule for a port of a device, rules of other devices are left alone.
+    fn rule(
+        &self,
+        api: &FritzClient,
+        client: &Tr064Client,
+        args: &RuleArgs,
+    ) -> AnyError<PortMapping> {
+        let (_, ip) = device_ip(api, &args.device)?;
+        port_mappings(client)?
+            .into_iter()
+            .find(|mapping| {
+                mapping.external_port == args.port
+                    && mapping.protocol == args.protocol.as_str()
+                    && mapping.internal_client == ip
+            })
+            .ok_or_else(|| {
+                format!(
+                    "No rule for {}/{} of {}!",
+                    args.port,
+                    args.protocol.as_str(),
+                    args.device
+                )
+                .into()
+            })
+    }
+
+    fn remove(&self, api: &FritzClient, args: &RuleArgs) -> AnyError<()> {
+        let client = api.tr064()?;
+        self.rule(api, &client, args)?.delete(&client)?;
+        println!("Removed {}/{}.", args.port, args.protocol.as_str());
+        Ok(())
+    }
+
+    fn set_enabled(&self, api: &FritzClient, args: &RuleArgs, enabled: bool) -> AnyError<()> {
+        let client = api.tr064()?;
+        let mut mapping = self.rule(api, &client, args)?;
+        mapping.enabled = enabled;
+        // Adding an existing rule again updates it.
+        mapping.add(&client)?;
+        println!(
+            "{} {}/{}.",
+            if enabled { "Enabled" } else { "Disabled" },
+            args.port,
+            args.protocol.as_str()
+        );
+        Ok(())
+    }
+
+    pub(super) fn run(&self, api: &FritzClient) -> AnyError<()> {
+        match &self.command {
+            Commands::List(args) => self.list(api, args),
+            Commands::Add(args) => self.add(api, args),
+            Commands::Remove(args) => self.remove(api, args),
+            Commands::Enable(args) => self.set_enabled(api, args, true),
+            Commands::Disable(args) => self.set_enabled(api, args, false),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use super::{parse_interval, AnyError, Device, FritzApiFunctions, FritzClient};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::PathBuf,
+    process::Command,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// An allowed device of the `--known` file, matched by MAC or else by name.
+#[derive(Debug, Default, Deserialize)]
+struct KnownDevice {
+    name: Option<String>,
+    mac: Option<String>,
+}
+
+/// The `--known` file, e.g. `[[device]]` tables with a `name` and `mac`.
+#[derive(Debug, Default, Deserialize)]
+struct KnownDevices {
+    #[serde(rename = "device", default)]
+    devices: Vec<KnownDevice>,
+}
+
+impl KnownDevices {
+    fn load(path: &PathBuf) -> AnyError<Self> {
+        let known: Self = toml::from_str(&fs::read_to_string(path)?)?;
+        Ok(known)
+    }
+
+    fn contains(&self, device: &Device) -> bool {
+        self.devices
+            .iter()
+            .any(|known| match (&known.mac, &device.mac) {
+                (Some(known), Some(mac)) => known.eq_ignore_ascii_case(mac),
+                (Some(_), None) => false,
+                (None, _) => known.name.is_some() && known.name == device.name,
+            })
+    }
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Presence {
+    Join,
+    Leave,
+}
+
+#[derive(Serialize)]
+struct Event {
+    time: String,
+    event: Presence,
+    name: String,
+    mac: String,
+    ip: String,
+    /// Only with `--known`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    known: Option<bool>,
+}
+
+#[derive(clap::Parser)]
+struct WatchArgs {
+    /// How often to ask the box.
+    #[arg(long, default_value = "30s", value_parser = parse_interval)]
+    interval: humantime::Duration,
+    /// Run this shell command per event instead of printing JSON lines. It
+    /// gets DEVICE_EVENT, DEVICE_NAME, DEVICE_MAC, DEVICE_IP and DEVICE_KNOWN.
+    #[arg(long)]
+    hook: Option<String>,
+    /// A TOML file of allowed devices, others are flagged as unknown.
+    #[arg(long)]
+    known: Option<PathBuf>,
+}
+
+#[derive(clap::Subcommand)]
+enum Commands {
+    /// Print or hook the devices joining and leaving the network. The devices
+    /// online at the start join right away.
+    Watch(WatchArgs),
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+/// The active devices by UID.
+fn online(api: &FritzClient) -> AnyError<BTreeMap<String, Device>> {
+    let devices = api.poll_devices()?.data.unwrap_or_default();
+    Ok(devices
+        .active
+        .into_iter()
+        .flatten()
+        .map(|device| (device.uid.clone(), device))
+        .collect())
+}
+
+fn emit(args: &WatchArgs, event: &Event) -> AnyError<()> {
+    let Some(hook) = &args.hook else {
+        println!("{}", serde_json::to_string(event)?);
+        return Ok(());
+    };
+    let presence = match event.event {
+        Presence::Join => "join",
+        Presence::Leave => "leave",
+    };
+    let known = match event.known {
+        Some(true) => "1",
+        Some(false) => "0",
+        None => "",
+    };
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(hook)
+        .env("DEVICE_EVENT", presence)
+        .env("DEVICE_NAME", &event.name)
+        .env("DEVICE_MAC", &event.mac)
+        .env("DEVICE_IP", &event.ip)
+        .env("DEVICE_KNOWN", known)
+        .status()?;
+    if !status.success() {
+        eprintln!("The hook failed for {}: {status}", event.name);
+    }
+    Ok(())
+}
+
+impl Cli {
+    fn watch(&self, api: &FritzClient, args: &WatchArgs) -> AnyError<()> {
+        let known = args.known.as_ref().map(KnownDevices::load).transpose()?;
+        // A session of our own, to log in again once the box dropped it.
+        let mut api = FritzClient::new_with_config(api.config.clone());
+        api.login()?;
+        let mut present = BTreeMap::new();
+        loop {
+            let devices = match online(&api).or_else(|_| {
+                api.login()?;
+                online(&api)
+            }) {
+                Ok(devices) => devices,
+                Err(err) => {
+                    eprintln!("Could not poll the devices: {err}");
+                    thread::sleep(Duration::from(args.interval));
+                    continue;
+                }
+            };
+            let time = humantime::format_rfc3339_seconds(SystemTime::now()).to_string();
+            let joined = devices
+                .iter()
+                .filter(|(uid, _)| !present.contains_key(*uid))
+                .map(|(_, device)| (Presence::Join, device));
+            let left = present
+                .iter()
+                .filter(|(uid, _)| !devices.contains_key(*uid))
+                .map(|(_, device)| (Presence::Leave, device));
+            for (presence, device) in joined.chain(left) {
+                let event = Event {
+                    time: time.clone(),
+                    event: presence,
+                    name: device.name.clone().unwrap_or_default(),
+                    mac: device.mac.clone().unwrap_or_default(),
+                    ip: device
+                        .ipv4
+                        .as_ref()
+                        .map(|ipv4| ipv4.ip.clone())
+                        .unwrap_or_default(),
+                    known: known.as_ref().map(|known| known.contains(device)),
+                };
+                if let Err(err) = emit(args, &event) {
+                    eprintln!("Could not emit an event for {}: {err}", event.name);
+                }
+            }
+            present = devices;
+            thread::sleep(Duration::from(args.interval));
+        }
+    }
+
+    pub(super) fn run(&self, api: &FritzClient) -> AnyError<()> {
+        match &self.command {
+            Commands::Watch(args) => self.watch(api, args),
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use super::{AnyError, FritzClient};
+use crate::table;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+const HOMEAUTOSWITCH_PATH: &str = "/webservices/homeautoswitch.lua";
+
+/// Thermostat targets are sent in half degrees, with two magic values.
+const HKR_OFF: u8 = 253;
+const HKR_ON: u8 = 254;
+const HKR_MIN_CELSIUS: f64 = 8.0;
+const HKR_MAX_CELSIUS: f64 = 28.0;
+
+#[derive(Debug, Default, Deserialize)]
+struct DeviceList {
+    #[serde(rename = "device", default)]
+    devices: Vec<SmartDevice>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SmartDevice {
+    identifier: String,
+    productname: Option<String>,
+    present: Option<u8>,
+    name: Option<String>,
+    switch: Option<Switch>,
+    powermeter: Option<PowerMeter>,
+    temperature: Option<Temperature>,
+    hkr: Option<Hkr>,
+}
+
+impl SmartDevice {
+    /// The AIN as the commands take it, without the blank in the middle.
+    fn ain(&self) -> String {
+        self.identifier.replace(' ', "")
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Switch {
+    state: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PowerMeter {
+    /// In mW.
+    power: Option<u64>,
+    /// In Wh.
+    energy: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Temperature {
+    /// In 0.1 °C, already corrected by the offset.
+    celsius: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Hkr {
+    /// The target temperature in 0.5 °C.
+    tsoll: Option<u8>,
+}
+
+fn format_hkr(value: u8) -> String {
+    match value {
+        HKR_OFF => "off".into(),
+        HKR_ON => "on".into(),
+        value => format!("{:.1}", f64::from(value) / 2.0),
+    }
+}
+
+#[derive(Debug)]
+struct NoSuchDevice(String);
+
+impl fmt::Display for NoSuchDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "No smart home device named {}!", self.0)
+    }
+}
+
+impl std::error::Error for NoSuchDevice {}
+
+impl FritzClient<'_> {
+    /// Send a command to the AHA HTTP interface, it answers in plain text or XML.
+    fn homeautoswitch(&self, command: &str, extra: &[(&str, &str)]) -> AnyError<String> {
+        let res = self
+            .client
+            .get(format!(
+                "{base}{HOMEAUTOSWITCH_PATH}",
+                base = self.config.base_url
+            ))
+            .query(&[("sid", self.session.sid.as_str()), ("switchcmd", command)])
+            .query(extra)
+            .send()?
+            .error_for_status()?
+            .text()?;
+        Ok(res)
+    }
+
+    fn smart_devices(&self) -> AnyError<DeviceList> {
+        let res = self.homeautoswitch("getdevicelistinfos", &[])?;
+        let list: DeviceList = serde_xml_rs::from_str(&res)?;
+        Ok(list)
+    }
+
+    /// Find a device by its name or AIN, with or without the blank.
+    fn smart_device(&self, name: &str) -> AnyError<SmartDevice> {
+        let wanted = name.replace(' ', "");
+        self.smart_devices()?
+            .devices
+            .into_iter()
+            .find(|d| d.ain() == wanted || d.name.as_deref() == Some(name))
+            .ok_or_else(|| NoSuchDevice(name.to_string()).into())
+    }
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum SwitchState {
+    On,
+    Off,
+    Toggle,
+}
+
+#[derive(clap::Parser)]
+struct ListArgs {}
+
+#[derive(clap::Parser)]
+struct SwitchArgs {
+    /// The name or AIN of the device.
+    device: String,
+    #[arg(value_enum)]
+    state: SwitchState,
+}
+
+#[derive(clap::Parser)]
+struct SetTempArgs {
+    /// The name or AIN of the thermostat.
+    device: String,
+    /// The target in °C (8 to 28, in steps of 0.5), or `on`/`off`.
+    #[arg(value_parser = parse_target)]
+    target: u8,
+}
+
+fn parse_target(value: &str) -> Result<u8, String> {
+    match value {
+        "on" => Ok(HKR_ON),
+        "off" => Ok(HKR_OFF),
+        value => {
+            let celsius: f64 = value
+                .parse()
+                .map_err(|_| format!("invalid target: {value}"))?;
+            if !(HKR_MIN_CELSIUS..=HKR_MAX_CELSIUS).contains(&celsius) {
+                return Err(format!(
+                    "target must be between {HKR_MIN_CELSIUS} and {HKR_MAX_CELSIUS}"
+                ));
+            }
+            Ok((celsius * 2.0).round() as u8)
+        }
+    }
+}
+
+#[derive(clap::Subcommand)]
+enum Commands {
+    /// List all smart home devices with their readings.
+    List(ListArgs),
+    /// Switch a smart plug.
+    Switch(SwitchArgs),
+    /// Set the target temperature of a thermostat.
+    SetTemp(SetTempArgs),
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct SmartDeviceRow {
+    name: String,
+    ain: String,
+    model: String,
+    present: String,
+    switch: String,
+    power: String,
+    energy: String,
+    temperature: String,
+    target: String,
+}
+
+impl<'a> table::TableRow<'a> for SmartDeviceRow {}
+
+impl Cli {
+    fn list(&self, api: &FritzClient, _args: &ListArgs) -> AnyError<()> {
+        let mut rows = vec![];
+        for device in api.smart_devices()?.devices {
+            let mut row = SmartDeviceRow {
+                ain: device.ain(),
+                ..Default::default()
+            };
+            row.name = device.name.unwrap_or_default();
+            row.model = device.productname.unwrap_or_default();
+            row.present = (if device.present == Some(1) {
+                "Yes"
+            } else {
+                "No"
+            })
+            .to_string();
+            if let Some(state) = device.switch.and_then(|s| s.state) {
+                row.switch = match state.as_str() {
+                    "1" => "on".into(),
+                    "0" => "off".into(),
+                    _ => String::new(),
+                };
+            }
+            if let Some(meter) = device.powermeter {
+                if let Some(power) = meter.power {
+                    row.power = format!("{:.2} W", power as f64 / 1000.0);
+                }
+                if let Some(energy) = meter.energy {
+                    row.energy = format!("{:.3} kWh", energy as f64 / 1000.0);
+                }
+            }
+            if let Some(celsius) = device.temperature.and_then(|t| t.celsius) {
+                row.temperature = format!("{:.1} °C", celsius as f64 / 10.0);
+            }
+            if let Some(tsoll) = device.hkr.and_then(|h| h.tsoll) {
+                row.target = format_hkr(tsoll);
+            }
+            rows.push(row);
+        }
+        println!("{}", table::Renderer::default().to_string(&rows));
+        Ok(())
+    }
+
+    fn switch(&self, api: &FritzClient, args: &SwitchArgs) -> AnyError<()> {
+        let device = api.smart_device(&args.device)?;
+        let command = match args.state {
+            SwitchState::On => "setswitchon",
+            SwitchState::Off => "setswitchoff",
+            SwitchState::Toggle => "setswitchtoggle",
+        };
+        let state = api.homeautoswitch(command, &[("ain", device.ain().as_str())])?;
+        println!(
+            "{}: {}",
+            device.name.unwrap_or_default(),
+            match state.trim() {
+                "1" => "on",
+                "0" => "off",
+                _ => "unknown",
+            }
+        );
+        Ok(())
+    }
+
+    fn set_temp(&self, api: &FritzClient, args: &SetTempArgs) -> AnyError<()> {
+        let device = api.smart_device(&args.device)?;
+        let param = args.target.to_string();
+        api.homeautoswitch(
+            "sethkrtsoll",
+            &[("ain", device.ain().as_str()), ("param", param.as_str())],
+        )?;
+        println!(
+            "{}: target {}",
+            device.name.unwrap_or_default(),
+            format_hkr(args.target)
+        );
+        Ok(())
+    }
+
+    pub(super) fn run(&self, api: &FritzClient) -> AnyError<()> {
+        match &self.command {
+            Commands::List(args) => self.list(api, args),
+            Commands::Switch(args) => self.switch(api, args),
+            Commands::SetTemp(args) => self.set_temp(api, args),
+        }
+    }
+}
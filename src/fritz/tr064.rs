@@ -0,0 +1,566 @@
+use super::{AnyError, Config, Device, DeviceData, Devices, FritzClient, IpV4};
+use crate::table;
+use core::fmt;
+use reqwest as rw;
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, str::FromStr};
+use xml::reader::{EventReader, XmlEvent};
+
+/// The port the TR-064 interface listens on, next to the web UI.
+const TR064_PORT: u16 = 49000;
+const DESCRIPTION_PATH: &str = "/tr64desc.xml";
+const SERVICE_TYPE_PREFIX: &str = "urn:dslforum-org:service:";
+
+#[derive(Debug, Deserialize)]
+struct Description {
+    device: DeviceDescription,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceDescription {
+    #[serde(rename = "serviceList")]
+    service_list: Option<ServiceList>,
+    #[serde(rename = "deviceList")]
+    device_list: Option<DeviceList>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServiceList {
+    #[serde(rename = "service", default)]
+    services: Vec<Service>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceList {
+    #[serde(rename = "device", default)]
+    devices: Vec<DeviceDescription>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(super) struct Service {
+    #[serde(rename = "serviceType")]
+    pub(super) service_type: String,
+    #[serde(rename = "controlURL")]
+    control_url: String,
+    #[serde(rename = "SCPDURL")]
+    scpd_url: String,
+}
+
+impl Service {
+    /// The service type without the common urn prefix, e.g. `WANIPConnection:1`.
+    pub(super) fn short_type(&self) -> &str {
+        self.service_type
+            .strip_prefix(SERVICE_TYPE_PREFIX)
+            .unwrap_or(&self.service_type)
+    }
+
+    /// Match by full type, short type or the short type without version.
+    fn matches(&self, name: &str) -> bool {
+        let short = self.short_type();
+        self.service_type == name
+            || short == name
+            || short.rsplit_once(':').is_some_and(|(n, _)| n == name)
+    }
+}
+
+fn flatten_services(device: DeviceDescription, services: &mut Vec<Service>) {
+    if let Some(list) = device.service_list {
+        services.extend(list.services);
+    }
+    for device in device.device_list.into_iter().flat_map(|l| l.devices) {
+        flatten_services(device, services);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Scpd {
+    #[serde(rename = "actionList")]
+    action_list: Option<ActionList>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ActionList {
+    #[serde(rename = "action", default)]
+    actions: Vec<Action>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct Action {
+    pub(super) name: String,
+    #[serde(rename = "argumentList")]
+    argument_list: Option<ArgumentList>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArgumentList {
+    #[serde(rename = "argument", default)]
+    arguments: Vec<Argument>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Argument {
+    name: String,
+    direction: String,
+}
+
+impl Action {
+    /// The names of the arguments in the given direction, `in` or `out`.
+    pub(super) fn arguments(&self, direction: &str) -> Vec<&str> {
+        self.argument_list
+            .iter()
+            .flat_map(|list| &list.arguments)
+            .filter(|argument| argument.direction == direction)
+            .map(|argument| argument.name.as_str())
+            .collect()
+    }
+}
+
+/// The out arguments of an action, in the order the box sent them.
+#[derive(Debug, Default)]
+pub(super) struct Arguments(pub(super) Vec<(String, String)>);
+
+impl Arguments {
+    pub(super) fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub(super) fn parse<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.get(name)?.parse().ok()
+    }
+
+    /// Booleans are transported as `0` or `1`.
+    pub(super) fn flag(&self, name: &str) -> bool {
+        self.get(name) == Some("1")
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct Tr064Error {
+    code: Option<String>,
+    description: String,
+}
+
+impl fmt::Display for Tr064Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "TR-064 error {code}: {}", self.description),
+            None => write!(f, "TR-064 error: {}", self.description),
+        }
+    }
+}
+
+impl std::error::Error for Tr064Error {}
+
+impl Tr064Error {
+    fn boxed(description: impl Into<String>) -> Box<Self> {
+        Box::new(Self {
+            code: None,
+            description: description.into(),
+        })
+    }
+}
+
+/// Parse a SOAP response body into the out arguments, or the fault it carries.
+fn parse_envelope(body: &str) -> AnyError<Arguments> {
+    let mut arguments = Arguments::default();
+    let mut path: Vec<String> = vec![];
+    let mut text = String::new();
+    let mut code = None;
+    let mut description = None;
+    let mut fault = false;
+    for event in EventReader::from_str(body) {
+        match event? {
+            XmlEvent::StartElement { name, .. } => {
+                if name.local_name == "Fault" {
+                    fault = true;
+                }
+                path.push(name.local_name);
+                text.clear();
+            }
+            XmlEvent::Characters(chars) | XmlEvent::CData(chars) => text.push_str(&chars),
+            XmlEvent::EndElement { name } => {
+                // Envelope > Body > ActionResponse > Argument
+                if !fault && path.len() == 4 {
+                    arguments.0.push((name.local_name.clone(), text.clone()));
+                }
+                match name.local_name.as_str() {
+                    "errorCode" => code = Some(text.clone()),
+                    "errorDescription" => description = Some(text.clone()),
+                    "faultstring" if description.is_none() => description = Some(text.clone()),
+                    _ => {}
+                }
+                path.pop();
+                text.clear();
+            }
+            _ => {}
+        }
+    }
+    if fault {
+        return Err(Box::new(Tr064Error {
+            code,
+            description: description.unwrap_or_else(|| "unknown fault".into()),
+        }));
+    }
+    Ok(arguments)
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[derive(Debug, Deserialize)]
+struct HostList {
+    #[serde(rename = "Item", default)]
+    items: Vec<HostItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HostItem {
+    #[serde(rename = "IPAddress")]
+    ip: Option<String>,
+    #[serde(rename = "MACAddress")]
+    mac: Option<String>,
+    #[serde(rename = "Active")]
+    active: Option<String>,
+    #[serde(rename = "HostName")]
+    name: Option<String>,
+    #[serde(rename = "InterfaceType")]
+    interface: Option<String>,
+    #[serde(rename = "X_AVM-DE_Model")]
+    model: Option<String>,
+}
+
+pub(super) struct Tr064Client {
+    base_url: String,
+    username: String,
+    password: String,
+    client: rw::blocking::Client,
+    services: Vec<Service>,
+    /// The last digest challenge, reused until the box asks again.
+    challenge: RefCell<Option<digest_auth::WwwAuthenticateHeader>>,
+}
+
+impl Tr064Client {
+    /// Discover the services of the box. Unless configured otherwise the interface
+    /// is expected on the same host as the web UI.
+    pub(super) fn discover(config: &Config) -> AnyError<Self> {
+        let base_url = match config.tr064_url {
+            Some(url) => url.trim_end_matches('/').to_string(),
+            None => {
+                let mut url = rw::Url::parse(config.base_url)?;
+                url.set_port(Some(TR064_PORT))
+                    .map_err(|_| Tr064Error::boxed("FRITZ_URL is not a valid base url"))?;
+                url.set_path("");
+                url.as_str().trim_end_matches('/').to_string()
+            }
+        };
+        let client = rw::blocking::Client::new();
+        let raw = client
+            .get(format!("{base_url}{DESCRIPTION_PATH}"))
+            .send()?
+            .error_for_status()?
+            .text()?;
+        let description: Description = serde_xml_rs::from_str(&raw)?;
+        let mut services = vec![];
+        flatten_services(description.device, &mut services);
+        Ok(Self {
+            base_url,
+            username: config.username.to_string(),
+            password: config.password.to_string(),
+            client,
+            services,
+            challenge: RefCell::new(None),
+        })
+    }
+
+    pub(super) fn service(&self, name: &str) -> AnyError<&Service> {
+        match self.services.iter().find(|service| service.matches(name)) {
+            Some(service) => Ok(service),
+            None => Err(Tr064Error::boxed(format!("service {name} not available"))),
+        }
+    }
+
+    pub(super) fn services(&self) -> &[Service] {
+        &self.services
+    }
+
+    /// Fetch the actions a service offers from its service description.
+    pub(super) fn actions(&self, service: &Service) -> AnyError<Vec<Action>> {
+        let raw = self.fetch(&service.scpd_url)?;
+        let scpd: Scpd = serde_xml_rs::from_str(&raw)?;
+        Ok(scpd.action_list.map(|l| l.actions).unwrap_or_default())
+    }
+
+    fn authorization(&self, path: &str, body: &str) -> AnyError<Option<String>> {
+        let mut challenge = self.challenge.borrow_mut();
+        let Some(prompt) = challenge.as_mut() else {
+            return Ok(None);
+        };
+        let context = digest_auth::AuthContext::new_post(
+            self.username.as_str(),
+            self.password.as_str(),
+            path,
+            Some(body.as_bytes()),
+        );
+        Ok(Some(prompt.respond(&context)?.to_header_string()))
+    }
+
+    /// Send a SOAP request with digest auth. The first request, or one with a
+    /// stale nonce, is answered with a new challenge and then repeated.
+    fn post(&self, service: &Service, action: &str, body: String) -> AnyError<String> {
+        for _ in 0..2 {
+            let mut request = self
+                .client
+                .post(format!("{}{}", self.base_url, service.control_url))
+                .header("Content-Type", "text/xml; charset=\"utf-8\"")
+                .header(
+                    "SOAPAction",
+                    format!("\"{}#{action}\"", service.service_type),
+                )
+                .body(body.clone());
+            if let Some(authorization) = self.authorization(&service.control_url, &body)? {
+                request = request.header("Authorization", authorization);
+            }
+            let response = request.send()?;
+            if response.status() == rw::StatusCode::UNAUTHORIZED {
+                let header = response
+                    .headers()
+                    .get("WWW-Authenticate")
+                    .and_then(|h| h.to_str().ok())
+                    .ok_or_else(|| Tr064Error::boxed("unauthorized without challenge"))?;
+                *self.challenge.borrow_mut() = Some(digest_auth::parse(header)?);
+                continue;
+            }
+            return Ok(response.text()?);
+        }
+        Err(Tr064Error::boxed("authentication failed").into())
+    }
+
+    /// Invoke an action of a service with the given in arguments.
+    pub(super) fn call(
+        &self,
+        service: &str,
+        action: &str,
+        arguments: &[(&str, &str)],
+    ) -> AnyError<Arguments> {
+        let service = self.service(service)?;
+        let arguments: String = arguments
+            .iter()
+            .map(|(key, value)| format!("<{key}>{}</{key}>", escape(value)))
+            .collect();
+        let body = format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"utf-8\"?>",
+                "<s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" ",
+                "s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">",
+                "<s:Body><u:{action} xmlns:u=\"{service_type}\">{arguments}</u:{action}>",
+                "</s:Body></s:Envelope>"
+            ),
+            action = action,
+            service_type = service.service_type,
+            arguments = arguments,
+        );
+        parse_envelope(&self.post(service, action, body)?)
+    }
+
+    /// Fetch a document the box handed out a path for, e.g. the host list.
+    pub(super) fn fetch(&self, path: &str) -> AnyError<String> {
+        let url = if path.starts_with("http") {
+            path.to_string()
+        } else {
+            format!("{}{path}", self.base_url)
+        };
+        Ok(self.client.get(url).send()?.error_for_status()?.text()?)
+    }
+
+    pub(super) fn reboot(&self) -> AnyError<bool> {
+        self.call("DeviceConfig:1", "Reboot", &[])?;
+        Ok(true)
+    }
+
+    pub(super) fn disconnect(&self) -> AnyError<()> {
+        self.call("WANIPConnection:1", "ForceTermination", &[])?;
+        Ok(())
+    }
+
+    pub(super) fn connect(&self) -> AnyError<()> {
+        self.call("WANIPConnection:1", "RequestConnection", &[])?;
+        Ok(())
+    }
+
+    pub(super) fn external_ip(&self) -> AnyError<String> {
+        let out = self.call("WANIPConnection:1", "GetExternalIPAddress", &[])?;
+        Ok(out.get("NewExternalIPAddress").unwrap_or_default().into())
+    }
+
+    /// Query the devices from the `Hosts` service, in the shape the web UI returns them.
+    pub(super) fn devices(&self) -> AnyError<Devices> {
+        let out = self.call("Hosts:1", "X_AVM-DE_GetHostListPath", &[])?;
+        let path = out
+            .get("NewX_AVM-DE_HostListPath")
+            .ok_or_else(|| Tr064Error::boxed("no host list path"))?;
+        let list: HostList = serde_xml_rs::from_str(&self.fetch(path)?)?;
+        let mut data = DeviceData::default();
+        for item in list.items {
+            let device = Device {
+                // The host list has no UID, the MAC is as stable.
+                uid: item.mac.clone().unwrap_or_default(),
+                ipv4: item
+                    .ip
+                    .filter(|ip| !ip.is_empty())
+                    .map(|ip| IpV4 { ip, lastused: None }),
+                mac: item.mac,
+                model: item.model.filter(|model| !model.is_empty()),
+                name: item.name,
+                typ: item.interface,
+                ..Default::default()
+            };
+            let list = match item.active.as_deref() {
+                Some("1") => &mut data.active,
+                _ => &mut data.passive,
+            };
+            list.get_or_insert_with(Vec::new).push(device);
+        }
+        Ok(Devices {
+            pid: String::new(),
+            data: Some(data),
+        })
+    }
+}
+
+#[derive(clap::Parser)]
+struct ListArgs {
+    /// Only list the actions of this service, e.g. `WANIPConnection:1`.
+    service: Option<String>,
+    /// How to render the actions.
+    #[arg(short, long, value_enum, default_value_t)]
+    output: table::Format,
+}
+
+#[derive(clap::Parser)]
+struct CallArgs {
+    /// The service, e.g. `DeviceInfo:1` or `urn:dslforum-org:service:DeviceInfo:1`.
+    service: String,
+    /// The action to invoke, e.g. `GetInfo`.
+    action: String,
+    /// In arguments as `key=value`. The `New` prefix of the names can be left out.
+    #[arg(value_parser = parse_argument)]
+    arguments: Vec<(String, String)>,
+    /// How to render the out arguments.
+    #[arg(short, long, value_enum, default_value_t)]
+    output: table::Format,
+}
+
+fn parse_argument(value: &str) -> Result<(String, String), String> {
+    let (key, value) = value
+        .split_once('=')
+        .ok_or_else(|| format!("invalid argument, expected key=value: {value}"))?;
+    let key = match key.starts_with("New") {
+        true => key.to_string(),
+        false => format!("New{key}"),
+    };
+    Ok((key, value.to_string()))
+}
+
+#[derive(clap::Subcommand)]
+enum Commands {
+    /// List all services and actions with their arguments.
+    List(ListArgs),
+    /// Invoke any action and print its out arguments.
+    Call(CallArgs),
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct ActionRow {
+    service: String,
+    action: String,
+    #[serde(rename = "IN")]
+    inputs: String,
+    #[serde(rename = "OUT")]
+    outputs: String,
+}
+
+impl<'a> table::TableRow<'a> for ActionRow {}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct ArgumentRow {
+    name: String,
+    value: String,
+}
+
+impl<'a> table::TableRow<'a> for ArgumentRow {}
+
+impl Cli {
+    fn list(&self, client: &Tr064Client, args: &ListArgs) -> AnyError<()> {
+        let services = match &args.service {
+            Some(name) => vec![client.service(name)?],
+            None => client.services().iter().collect(),
+        };
+        let mut rows = vec![];
+        for service in services {
+            for action in client.actions(service)? {
+                rows.push(ActionRow {
+                    service: service.short_type().to_string(),
+                    inputs: action.arguments("in").join(", "),
+                    outputs: action.arguments("out").join(", "),
+                    action: action.name,
+                });
+            }
+        }
+        println!("{}", table::Renderer::default().render(args.output, &rows));
+        Ok(())
+    }
+
+    fn call(&self, client: &Tr064Client, args: &CallArgs) -> AnyError<()> {
+        let arguments: Vec<_> = args
+            .arguments
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        let out = client.call(&args.service, &args.action, &arguments)?;
+        match args.output {
+            // The whole response is a single row here.
+            table::Format::Json => {
+                let object: serde_json::Map<_, _> = out
+                    .0
+                    .into_iter()
+                    .map(|(name, value)| (name, serde_json::Value::String(value)))
+                    .collect();
+                println!("{}", serde_json::to_string(&object)?);
+            }
+            format => {
+                let rows: Vec<_> = out
+                    .0
+                    .into_iter()
+                    .map(|(name, value)| ArgumentRow { name, value })
+                    .collect();
+                println!("{}", table::Renderer::default().render(format, &rows));
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn run(&self, api: &FritzClient) -> AnyError<()> {
+        let client = api.tr064()?;
+        match &self.command {
+            Commands::List(args) => self.list(&client, args),
+            Commands::Call(args) => self.call(&client, args),
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use super::{parse_interval, AnyError, FritzClient};
+use crate::table;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{collections::VecDeque, io::Write, thread, time::Duration};
+
+const ONLINE_MONITOR_SERVICE: &str = "WANCommonInterfaceConfig:1";
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The counters come as strings or numbers, depending on the firmware.
+fn number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(match value {
+        serde_json::Value::Number(n) => n.as_u64().unwrap_or_default(),
+        serde_json::Value::String(s) => s.parse().unwrap_or_default(),
+        _ => 0,
+    })
+}
+
+/// The bytes of a period, split in the high and low 32 bits.
+#[derive(Debug, Default, Deserialize)]
+pub(super) struct Counter {
+    #[serde(rename = "BytesSentHigh", deserialize_with = "number", default)]
+    sent_high: u64,
+    #[serde(rename = "BytesSentLow", deserialize_with = "number", default)]
+    sent_low: u64,
+    #[serde(rename = "BytesReceivedHigh", deserialize_with = "number", default)]
+    received_high: u64,
+    #[serde(rename = "BytesReceivedLow", deserialize_with = "number", default)]
+    received_low: u64,
+}
+
+impl Counter {
+    pub(super) fn sent(&self) -> u64 {
+        self.sent_high << 32 | self.sent_low
+    }
+
+    pub(super) fn received(&self) -> u64 {
+        self.received_high << 32 | self.received_low
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(super) struct CounterData {
+    pub(super) today: Option<Counter>,
+    pub(super) yesterday: Option<Counter>,
+    pub(super) this_week: Option<Counter>,
+    pub(super) this_month: Option<Counter>,
+    pub(super) last_month: Option<Counter>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(super) struct Counters {
+    pub(super) data: CounterData,
+}
+
+impl FritzClient<'_> {
+    /// Query the counters of the online monitor.
+    pub(super) fn counters(&self) -> AnyError<Counters> {
+        let res = self.data_lua("netCnt", &[("xhrId", "all")])?;
+        let counters: Counters = serde_json::from_str(&res)?;
+        Ok(counters)
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    let units = ["B", "kB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < units.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{bytes} B"),
+        unit => format!("{value:.2} {}", units[unit]),
+    }
+}
+
+fn format_rate(bytes_per_second: u64) -> String {
+    let bits = bytes_per_second as f64 * 8.0;
+    match bits {
+        bits if bits >= 1e6 => format!("{:.1} Mbit/s", bits / 1e6),
+        bits => format!("{:.1} kbit/s", bits / 1e3),
+    }
+}
+
+/// Scale the samples to the largest one.
+fn sparkline(samples: &VecDeque<u64>) -> String {
+    let max = samples.iter().copied().max().unwrap_or_default().max(1);
+    samples
+        .iter()
+        .map(|&sample| SPARKS[(sample * (SPARKS.len() as u64 - 1) / max) as usize])
+        .collect()
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Args {
+    /// Poll the current throughput instead of showing the counters.
+    #[arg(long)]
+    live: bool,
+    /// How often to poll with `--live`, the box samples every 5s.
+    #[arg(long, default_value = "5s", value_parser = parse_interval)]
+    interval: humantime::Duration,
+    /// How many samples the sparklines of `--live` show.
+    #[arg(long, default_value_t = 30, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    width: usize,
+    /// How to render the counters.
+    #[arg(short, long, value_enum, default_value_t)]
+    output: table::Format,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct CounterRow {
+    period: String,
+    received: String,
+    sent: String,
+    total: String,
+}
+
+impl<'a> table::TableRow<'a> for CounterRow {}
+
+fn counters(api: &FritzClient, args: &Args) -> AnyError<()> {
+    let data = api.counters()?.data;
+    let periods = [
+        ("today", data.today),
+        ("yesterday", data.yesterday),
+        ("this week", data.this_week),
+        ("this month", data.this_month),
+        ("last month", data.last_month),
+    ];
+    let rows: Vec<_> = periods
+        .into_iter()
+        .filter_map(|(period, counter)| Some((period, counter?)))
+        .map(|(period, counter)| CounterRow {
+            period: period.into(),
+            received: format_bytes(counter.received()),
+            sent: format_bytes(counter.sent()),
+            total: format_bytes(counter.received() + counter.sent()),
+        })
+        .collect();
+    println!("{}", table::Renderer::default().render(args.output, &rows));
+    Ok(())
+}
+
+/// The newest of the samples the box lists, newest first.
+fn current_sample(samples: Option<&str>) -> u64 {
+    samples
+        .and_then(|s| s.split(',').next())
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or_default()
+}
+
+fn live(api: &FritzClient, args: &Args) -> AnyError<()> {
+    let client = api.tr064()?;
+    let mut down = VecDeque::with_capacity(args.width);
+    let mut up = VecDeque::with_capacity(args.width);
+    loop {
+        let monitor = client.call(
+            ONLINE_MONITOR_SERVICE,
+            "X_AVM-DE_GetOnlineMonitor",
+            &[("NewSyncGroupIndex", "0")],
+        )?;
+        for (samples, key) in [
+            (&mut down, "Newds_current_bps"),
+            (&mut up, "Newus_current_bps"),
+        ] {
+            if samples.len() == args.width {
+                samples.pop_front();
+            }
+            samples.push_back(current_sample(monitor.get(key)));
+        }
+        print!(
+            "\r\x1b[K↓ {:>13} {}  ↑ {:>13} {}",
+            format_rate(down.back().copied().unwrap_or_default()),
+            sparkline(&down),
+            format_rate(up.back().copied().unwrap_or_default()),
+            sparkline(&up),
+        );
+        std::io::stdout().flush()?;
+        thread::sleep(Duration::from(args.interval));
+    }
+}
+
+pub(super) fn run(api: &FritzClient, args: &Args) -> AnyError<()> {
+    match args.live {
+        true => live(api, args),
+        false => counters(api, args),
+    }
+}
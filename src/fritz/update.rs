@@ -0,0 +1,123 @@
+use super::{format_elapsed, tr064::Tr064Client, wait_for, AnyError, FritzClient};
+use crate::{table, Failure};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+const UPDATE_SERVICE: &str = "UserInterface:1";
+const DEVICE_INFO_SERVICE: &str = "DeviceInfo:1";
+
+/// Versions are in the `154.07.57` form, the web UI leaves out the model.
+struct UpdateInfo {
+    installed: String,
+    available: Option<String>,
+    info_url: String,
+}
+
+impl UpdateInfo {
+    fn query(client: &Tr064Client) -> AnyError<Self> {
+        let installed = installed_version(client)?;
+        let update = client.call(UPDATE_SERVICE, "GetInfo", &[])?;
+        let available = update
+            .get("NewX_AVM-DE_Version")
+            .filter(|version| update.flag("NewUpgradeAvailable") && !version.is_empty())
+            .map(String::from);
+        Ok(Self {
+            installed,
+            available,
+            info_url: update.get("NewX_AVM-DE_InfoURL").unwrap_or_default().into(),
+        })
+    }
+}
+
+fn installed_version(client: &Tr064Client) -> AnyError<String> {
+    let info = client.call(DEVICE_INFO_SERVICE, "GetInfo", &[])?;
+    Ok(info.get("NewSoftwareVersion").unwrap_or_default().into())
+}
+
+#[derive(clap::Parser)]
+struct CheckArgs {}
+
+#[derive(clap::Parser)]
+struct InstallArgs {
+    /// Really install the update, the box is offline for a few minutes.
+    #[arg(long)]
+    yes: bool,
+    /// How long to wait for the box to come back with the new version.
+    #[arg(long, default_value = "15m")]
+    timeout: humantime::Duration,
+}
+
+#[derive(clap::Subcommand)]
+enum Commands {
+    /// Compare the installed and the available version, exits with 1 if an
+    /// update is pending.
+    Check(CheckArgs),
+    /// Install the available update and wait for the box to come back.
+    Install(InstallArgs),
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct UpdateRow {
+    installed: String,
+    available: String,
+    #[serde(rename = "RELEASE NOTES")]
+    release_notes: String,
+}
+
+impl<'a> table::TableRow<'a> for UpdateRow {}
+
+impl Cli {
+    fn check(&self, api: &FritzClient, _args: &CheckArgs) -> AnyError<()> {
+        let info = UpdateInfo::query(&api.tr064()?)?;
+        let pending = info.available.is_some();
+        let row = UpdateRow {
+            installed: info.installed,
+            available: info.available.unwrap_or_else(|| "up to date".into()),
+            release_notes: info.info_url,
+        };
+        println!("{}", table::Renderer::default().to_string(&[row]));
+        if pending {
+            return Err(Failure.into());
+        }
+        Ok(())
+    }
+
+    fn install(&self, api: &FritzClient, args: &InstallArgs) -> AnyError<()> {
+        if !args.yes {
+            return Err("Updating takes the box offline for a few minutes, pass --yes!".into());
+        }
+        let client = api.tr064()?;
+        let Some(available) = UpdateInfo::query(&client)?.available else {
+            println!("Already up to date.");
+            return Ok(());
+        };
+        client.call(UPDATE_SERVICE, "X_AVM-DE_DoUpdate", &[])?;
+        println!("Installing {available}, this can take some time...");
+        // The box is unreachable while it flashes and reboots, so every
+        // failure until then is expected.
+        let start = Instant::now();
+        wait_for(start + Duration::from(args.timeout), || {
+            let version = installed_version(&api.tr064().ok()?).ok()?;
+            (version == available).then_some(())
+        })?;
+        println!(
+            "The box is back with {available} after {}.",
+            format_elapsed(start.elapsed())
+        );
+        Ok(())
+    }
+
+    pub(super) fn run(&self, api: &FritzClient) -> AnyError<()> {
+        match &self.command {
+            Commands::Check(args) => self.check(api, args),
+            Commands::Install(args) => self.install(api, args),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use super::{tr064::Tr064Client, AnyError, FritzClient};
+use crate::table;
+use serde::Serialize;
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+const WAN_SERVICE: &str = "WANIPConnection:1";
+
+#[derive(Debug, Default)]
+struct WanInfo {
+    ipv4: String,
+    ipv6: String,
+    /// The prefix delegated to the LAN, e.g. `2001:db8:1::/56`.
+    prefix: String,
+    dns: Vec<String>,
+    uptime: Duration,
+}
+
+impl WanInfo {
+    /// Without IPv6 the box answers its actions with errors, which just
+    /// leave the fields empty.
+    fn query(client: &Tr064Client) -> AnyError<Self> {
+        let status = client.call(WAN_SERVICE, "GetStatusInfo", &[])?;
+        let dns = client.call(WAN_SERVICE, "X_AVM-DE_GetDNSServer", &[])?;
+        let mut info = Self {
+            ipv4: client.external_ip()?,
+            dns: ["NewIPv4DNSServer1", "NewIPv4DNSServer2"]
+                .into_iter()
+                .filter_map(|name| dns.get(name))
+                .filter(|server| !server.is_empty())
+                .map(String::from)
+                .collect(),
+            uptime: Duration::from_secs(status.parse("NewUptime").unwrap_or_default()),
+            ..Default::default()
+        };
+        if let Ok(ipv6) = client.call(WAN_SERVICE, "X_AVM-DE_GetExternalIPv6Address", &[]) {
+            info.ipv6 = ipv6
+                .get("NewExternalIPv6Address")
+                .unwrap_or_default()
+                .into();
+        }
+        if let Ok(prefix) = client.call(WAN_SERVICE, "X_AVM-DE_GetIPv6Prefix", &[]) {
+            info.prefix = match (prefix.get("NewIPv6Prefix"), prefix.get("NewPrefixLength")) {
+                (Some(prefix), Some(length)) if !prefix.is_empty() => format!("{prefix}/{length}"),
+                _ => String::new(),
+            };
+        }
+        if let Ok(dns) = client.call(WAN_SERVICE, "X_AVM-DE_GetIPv6DNSServer", &[]) {
+            info.dns.extend(
+                ["NewIPv6DNSServer1", "NewIPv6DNSServer2"]
+                    .into_iter()
+                    .filter_map(|name| dns.get(name))
+                    .filter(|server| !server.is_empty())
+                    .map(String::from),
+            );
+        }
+        Ok(info)
+    }
+}
+
+/// Append the addresses to the history, unless they are the same as last time.
+/// Lines are tab separated: timestamp, IPv4, IPv6 and prefix.
+fn append_history(path: &Path, info: &WanInfo) -> AnyError<bool> {
+    let addresses = format!("{}\t{}\t{}", info.ipv4, info.ipv6, info.prefix);
+    let history = fs::read_to_string(path).unwrap_or_default();
+    let last = history
+        .lines()
+        .last()
+        .and_then(|line| line.split_once('\t'))
+        .map(|(_, addresses)| addresses);
+    if last == Some(addresses.as_str()) {
+        return Ok(false);
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(
+        file,
+        "{}\t{addresses}",
+        humantime::format_rfc3339_seconds(SystemTime::now())
+    )?;
+    Ok(true)
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Args {
+    /// Append the addresses to this file whenever they changed.
+    #[arg(long)]
+    history: Option<PathBuf>,
+    /// How to render the addresses.
+    #[arg(short, long, value_enum, default_value_t)]
+    output: table::Format,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct WanRow {
+    ipv4: String,
+    ipv6: String,
+    prefix: String,
+    dns: String,
+    uptime: String,
+}
+
+impl<'a> table::TableRow<'a> for WanRow {}
+
+pub(super) fn run(api: &FritzClient, args: &Args) -> AnyError<()> {
+    let info = WanInfo::query(&api.tr064()?)?;
+    if let Some(path) = &args.history {
+        if append_history(path, &info)? {
+            eprintln!("Recorded the new addresses in {}.", path.display());
+        }
+    }
+    let row = WanRow {
+        ipv4: info.ipv4,
+        ipv6: info.ipv6,
+        prefix: info.prefix,
+        dns: info.dns.join(", "),
+        uptime: humantime::format_duration(info.uptime).to_string(),
+    };
+    println!("{}", table::Renderer::default().render(args.output, &[row]));
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use super::{tr064::Tr064Client, AnyError, FritzClient};
+use crate::table;
+use qrcode::{render::unicode::Dense1x2, QrCode};
+use serde::Serialize;
+use std::time::Duration;
+
+const WLAN_SERVICE: &str = "WLANConfiguration";
+
+/// A wireless network, one per `WLANConfiguration` service.
+struct Network {
+    service: String,
+    guest: bool,
+}
+
+/// The box numbers its networks by band, the guest network always comes last.
+fn networks(client: &Tr064Client) -> Vec<Network> {
+    let mut services: Vec<_> = client
+        .services()
+        .iter()
+        .map(|s| s.short_type())
+        .filter(|t| t.starts_with(&format!("{WLAN_SERVICE}:")))
+        .map(String::from)
+        .collect();
+    services.sort_by_key(|s| {
+        s.rsplit_once(':')
+            .and_then(|(_, n)| n.parse::<u32>().ok())
+            .unwrap_or_default()
+    });
+    let count = services.len();
+    services
+        .into_iter()
+        .enumerate()
+        .map(|(i, service)| Network {
+            service,
+            guest: count > 1 && i == count - 1,
+        })
+        .collect()
+}
+
+fn guest_network(client: &Tr064Client) -> AnyError<Network> {
+    networks(client)
+        .into_iter()
+        .find(|n| n.guest)
+        .ok_or_else(|| "The box has no guest network!".into())
+}
+
+/// Older firmware does not report the band, the channel tells it apart well enough.
+fn band(frequency: Option<&str>, channel: u32) -> &'static str {
+    match frequency {
+        Some("2400") => "2.4 GHz",
+        Some("5000") => "5 GHz",
+        Some("6000") => "6 GHz",
+        _ if channel <= 14 => "2.4 GHz",
+        _ => "5 GHz",
+    }
+}
+
+/// Escape the characters that have a meaning in the `WIFI:` scheme.
+fn escape_wifi(value: &str) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum GuestState {
+    On,
+    Off,
+}
+
+#[derive(clap::Parser)]
+struct StatusArgs {}
+
+#[derive(clap::Parser)]
+struct GuestArgs {
+    #[arg(value_enum)]
+    state: GuestState,
+    /// Turn the guest network off again after this long, e.g. `4h`.
+    #[arg(long)]
+    duration: Option<humantime::Duration>,
+}
+
+#[derive(clap::Parser)]
+struct QrArgs {
+    /// Share the guest network instead of the main one.
+    #[arg(long)]
+    guest: bool,
+}
+
+#[derive(clap::Subcommand)]
+enum Commands {
+    /// List all wireless networks.
+    Status(StatusArgs),
+    /// Turn the guest network on or off.
+    Guest(GuestArgs),
+    /// Print a QR code to join a network.
+    Qr(QrArgs),
+}
+
+#[derive(clap::Parser)]
+pub(super) struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct WlanRow {
+    network: String,
+    ssid: String,
+    enabled: String,
+    channel: String,
+    clients: String,
+    standard: String,
+}
+
+impl<'a> table::TableRow<'a> for WlanRow {}
+
+impl Cli {
+    /// Only the timer of the guest network goes through the web UI.
+    pub(super) fn needs_login(&self) -> bool {
+        matches!(
+            &self.command,
+            Commands::Guest(GuestArgs {
+                duration: Some(_),
+                ..
+            })
+        )
+    }
+
+    fn status(&self, api: &FritzClient, _args: &StatusArgs) -> AnyError<()> {
+        let client = api.tr064()?;
+        let mut rows = vec![];
+        for network in networks(&client) {
+            let info = client.call(&network.service, "GetInfo", &[])?;
+            let clients = client.call(&network.service, "GetTotalAssociations", &[])?;
+            let channel = info.parse("NewChannel").unwrap_or_default();
+            rows.push(WlanRow {
+                network: match network.guest {
+                    true => "guest".into(),
+                    false => band(info.get("NewX_AVM-DE_FrequencyBand"), channel).into(),
+                },
+                ssid: info.get("NewSSID").unwrap_or_default().into(),
+                enabled: (if info.flag("NewEnable") { "Yes" } else { "No" }).into(),
+                channel: channel.to_string(),
+                clients: clients
+                    .get("NewTotalAssociations")
+                    .unwrap_or_default()
+                    .into(),
+                standard: info.get("NewStandard").unwrap_or_default().into(),
+            });
+        }
+        println!("{}", table::Renderer::default().to_string(&rows));
+        Ok(())
+    }
+
+    fn guest(&self, api: &FritzClient, args: &GuestArgs) -> AnyError<()> {
+        if let (GuestState::Off, Some(_)) = (args.state, args.duration) {
+            return Err("A --duration only works with on!".into());
+        }
+        let client = api.tr064()?;
+        let network = guest_network(&client)?;
+        let enable = match args.state {
+            GuestState::On => "1",
+            GuestState::Off => "0",
+        };
+        client.call(&network.service, "SetEnable", &[("NewEnable", enable)])?;
+        // TR-064 has no timer, the one of the guest access page does the job.
+        if let (GuestState::On, Some(duration)) = (args.state, args.duration) {
+            let minutes = Duration::from(duration).as_secs().div_ceil(60).to_string();
+            api.data_lua(
+                "wGuest",
+                &[
+                    ("activate_guest_access", "on"),
+                    ("down_time_activ", "on"),
+                    ("down_time_value", minutes.as_str()),
+                    ("apply", ""),
+                ],
+            )?;
+            println!("Guest network is on for {duration}.");
+        } else {
+            println!(
+                "Guest network is {}.",
+                match args.state {
+                    GuestState::On => "on",
+                    GuestState::Off => "off",
+                }
+            );
+        }
+        Ok(())
+    }
+
+    fn qr(&self, api: &FritzClient, args: &QrArgs) -> AnyError<()> {
+        let client = api.tr064()?;
+        let network = match args.guest {
+            true => guest_network(&client)?,
+            false => networks(&client)
+                .into_iter()
+                .next()
+                .ok_or("The box has no wireless network!")?,
+        };
+        let info = client.call(&network.service, "GetInfo", &[])?;
+        let keys = client.call(&network.service, "GetSecurityKeys", &[])?;
+        let ssid = info.get("NewSSID").unwrap_or_default();
+        let password = keys.get("NewKeyPassphrase").unwrap_or_default();
+        let security = match info.get("NewBeaconType") {
+            Some("None") => "nopass",
+            Some("Basic") => "WEP",
+            _ => "WPA",
+        };
+        let data = match security {
+            "nopass" => format!("WIFI:T:nopass;S:{};;", escape_wifi(ssid)),
+            security => format!(
+                "WIFI:T:{security};S:{};P:{};;",
+                escape_wifi(ssid),
+                escape_wifi(password)
+            ),
+        };
+        // Inverted, so the code stays readable on dark terminals.
+        let code = QrCode::new(data)?
+            .render::<Dense1x2>()
+            .dark_color(Dense1x2::Light)
+            .light_color(Dense1x2::Dark)
+            .build();
+        println!("{code}");
+        println!("{ssid}");
+        Ok(())
+    }
+
+    pub(super) fn run(&self, api: &FritzClient) -> AnyError<()> {
+        match &self.command {
+            Commands::Status(args) => self.status(api, args),
+            Commands::Guest(args) => self.guest(api, args),
+            Commands::Qr(args) => self.qr(api, args),
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use clap::Parser;
+use std::{fmt, process::ExitCode};
 mod docker;
 mod fritz;
+mod metrics;
 mod table;
 
 #[derive(clap::Subcommand)]
@@ -9,8 +11,23 @@ enum Commands {
     Docker(docker::Cli),
     #[command(arg_required_else_help(true))]
     Fritz(fritz::Cli),
+    /// Serve FritzBox and docker metrics for Prometheus.
+    ServeMetrics(metrics::Cli),
 }
 
+/// A command failed on purpose, e.g. `doctor` found problems. It printed why
+/// already, so this only sets the exit code.
+#[derive(Debug)]
+pub(crate) struct Failure;
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed")
+    }
+}
+
+impl std::error::Error for Failure {}
+
 #[derive(clap::Parser)]
 #[command(version, about, long_about=None)]
 struct Cli {
@@ -18,13 +35,22 @@ struct Cli {
     command: Option<Commands>,
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
     let args = Cli::parse();
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Tokio?");
     if let Some(command) = args.command {
-        match command {
-            Commands::Docker(cli) => cli.run().map_err(|err| format!("{err}"))?,
-            Commands::Fritz(cli) => cli.run().map_err(|err| format!("{err}"))?,
+        let result = match command {
+            Commands::Docker(cli) => rt.block_on(cli.run()),
+            Commands::Fritz(cli) => cli.run(),
+            Commands::ServeMetrics(cli) => cli.run(&rt),
+        };
+        match result {
+            Err(err) if err.is::<Failure>() => return Ok(ExitCode::FAILURE),
+            result => result.map_err(|err| format!("{err}"))?,
         }
     }
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
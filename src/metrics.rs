@@ -0,0 +1,181 @@
+use crate::{docker, fritz};
+use std::{
+    fmt::Write as _,
+    io::{BufRead, BufReader, Read, Write as _},
+    net::{TcpListener, TcpStream},
+    time::Duration,
+};
+
+type AnyError<T> = Result<T, Box<dyn std::error::Error>>;
+
+/// Requests are served one by one, a stalled client must not block the next scrape.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Scrapers send a few short headers, anything bigger is not one.
+const MAX_REQUEST_BYTES: u64 = 16 * 1024;
+const MAX_HEADERS: usize = 100;
+
+#[derive(Clone, Copy)]
+pub(crate) enum Kind {
+    Gauge,
+    Counter,
+}
+
+/// Metrics in the Prometheus text format.
+#[derive(Default)]
+pub(crate) struct Metrics {
+    out: String,
+}
+
+impl Metrics {
+    /// Start a metric, all of its samples have to follow right after.
+    pub(crate) fn describe(&mut self, name: &str, kind: Kind, help: &str) {
+        let kind = match kind {
+            Kind::Gauge => "gauge",
+            Kind::Counter => "counter",
+        };
+        let _ = writeln!(self.out, "# HELP {name} {help}");
+        let _ = writeln!(self.out, "# TYPE {name} {kind}");
+    }
+
+    pub(crate) fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<_> = labels
+                .iter()
+                .map(|(key, value)| format!("{key}=\"{}\"", escape(value)))
+                .collect();
+            let _ = write!(self.out, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.out, " {value}");
+    }
+
+    /// A metric with just a single sample.
+    pub(crate) fn single(&mut self, name: &str, kind: Kind, help: &str, value: f64) {
+        self.describe(name, kind, help);
+        self.sample(name, &[], value);
+    }
+
+    fn append(&mut self, other: Metrics) {
+        self.out.push_str(&other.out);
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+#[derive(clap::Parser)]
+pub(crate) struct Cli {
+    /// The address to listen on, `:9100` listens on all interfaces.
+    #[arg(long, default_value = ":9100")]
+    listen: String,
+    /// Leave out the FritzBox metrics.
+    #[arg(long)]
+    no_fritz: bool,
+    /// Leave out the docker metrics.
+    #[arg(long)]
+    no_docker: bool,
+}
+
+impl Cli {
+    /// Collect all metrics. A failing source is reported by its `_up` metric
+    /// instead of failing the whole scrape.
+    fn scrape(&self, rt: &tokio::runtime::Runtime, fritz: Option<&mut fritz::Exporter>) -> String {
+        let mut metrics = Metrics::default();
+        if let Some(exporter) = fritz {
+            let mut fritz_metrics = Metrics::default();
+            let up = match exporter.collect(&mut fritz_metrics) {
+                Ok(()) => {
+                    metrics.append(fritz_metrics);
+                    1.0
+                }
+                Err(err) => {
+                    eprintln!("Could not scrape the FritzBox: {err}");
+                    0.0
+                }
+            };
+            metrics.single(
+                "fritz_up",
+                Kind::Gauge,
+                "Whether the FritzBox could be scraped.",
+                up,
+            );
+        }
+        if !self.no_docker {
+            let mut docker_metrics = Metrics::default();
+            let up = match rt.block_on(docker::collect_metrics(&mut docker_metrics)) {
+                Ok(()) => {
+                    metrics.append(docker_metrics);
+                    1.0
+                }
+                Err(err) => {
+                    eprintln!("Could not scrape docker: {err}");
+                    0.0
+                }
+            };
+            metrics.single(
+                "docker_up",
+                Kind::Gauge,
+                "Whether docker could be scraped.",
+                up,
+            );
+        }
+        metrics.out
+    }
+
+    fn respond(&self, stream: &mut TcpStream, body: impl FnOnce() -> String) -> AnyError<()> {
+        stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+        stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+        let mut reader = BufReader::new((&*stream).take(MAX_REQUEST_BYTES));
+        let mut request = String::new();
+        reader.read_line(&mut request)?;
+        // Read the headers, closing with unread data resets the connection.
+        let mut header = String::new();
+        for _ in 0..MAX_HEADERS {
+            if reader.read_line(&mut header)? <= 2 {
+                break;
+            }
+            header.clear();
+        }
+        let (status, body) = match request.split_whitespace().nth(1) {
+            Some("/metrics") => ("200 OK", body()),
+            _ => ("404 Not Found", "Try /metrics\n".to_string()),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\n\
+             Content-Type: text/plain; version=0.0.4\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn run(&self, rt: &tokio::runtime::Runtime) -> AnyError<()> {
+        let env = match self.no_fritz {
+            true => None,
+            false => Some(fritz::Env::from_env()?),
+        };
+        // The exporter keeps its session, so scrapes don't log in every time.
+        let mut exporter = env.as_ref().map(fritz::Exporter::new);
+        let addr = match self.listen.strip_prefix(':') {
+            Some(port) => format!("0.0.0.0:{port}"),
+            None => self.listen.clone(),
+        };
+        let listener = TcpListener::bind(&addr)?;
+        println!("Serving metrics on http://{addr}/metrics");
+        for stream in listener.incoming() {
+            let result = stream.map_err(Into::into).and_then(|mut stream| {
+                self.respond(&mut stream, || self.scrape(rt, exporter.as_mut()))
+            });
+            if let Err(err) = result {
+                eprintln!("Could not serve a request: {err}");
+            }
+        }
+        Ok(())
+    }
+}
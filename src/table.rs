@@ -34,6 +34,35 @@ pub(crate) trait TableRow<'a>: Serialize {
     }
 }
 
+/// How command output should be rendered.
+#[derive(Clone, Copy, Default, clap::ValueEnum)]
+pub(crate) enum Format {
+    /// An aligned table.
+    #[default]
+    Table,
+    /// One JSON object per row.
+    Json,
+    /// Comma separated values with a header.
+    Csv,
+}
+
+/// Quote a cell if it would otherwise break the line apart.
+fn csv_cell(content: &str) -> String {
+    if content.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", content.replace('"', "\"\""))
+    } else {
+        content.to_string()
+    }
+}
+
+fn csv_line(contents: &[String]) -> String {
+    contents
+        .iter()
+        .map(|content| csv_cell(content))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 pub(crate) struct Renderer {
     column_spacing: usize,
 }
@@ -45,6 +74,32 @@ impl Default for Renderer {
 }
 
 impl Renderer {
+    /// Render the rows in any format. Rows are separated, not terminated, by newlines.
+    pub(crate) fn render<'a, RowType: TableRow<'a>>(
+        &self,
+        format: Format,
+        rows: &[RowType],
+    ) -> String {
+        match format {
+            Format::Table => self.to_string(rows),
+            Format::Json => rows
+                .iter()
+                .filter_map(|row| serde_json::to_string(row).ok())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Format::Csv => {
+                let Some(first) = rows.first() else {
+                    return String::new();
+                };
+                let mut lines = vec![csv_line(&first.columns().unwrap_or_default())];
+                for row in rows {
+                    lines.push(csv_line(&row.cells().unwrap_or_default()));
+                }
+                lines.join("\n")
+            }
+        }
+    }
+
     pub(crate) fn to_string<'a, RowType: TableRow<'a>>(&self, rows: &[RowType]) -> String {
         use std::fmt::Write;
         let mut output = String::new();
@@ -62,7 +117,7 @@ impl Renderer {
         for column_index in 0..cols.len() {
             for i in (column_index..cells.len()).step_by(cols.len()) {
                 if let Some(content) = cells.get(i) {
-                    if content.len() > column_widths[column_index] {
+                    if content.chars().count() > column_widths[column_index] {
                         column_widths[column_index] = content.chars().count();
                     }
                 };
@@ -72,7 +127,7 @@ impl Renderer {
         for column_index in 0..cols.len() {
             let content = &cols[column_index];
             let width = column_widths[column_index];
-            let abs_width = width.saturating_sub(content.len()) + self.column_spacing;
+            let abs_width = width.saturating_sub(content.chars().count()) + self.column_spacing;
             let _ = output.write_fmt(format_args!("{}{}", content, " ".repeat(abs_width)));
         }
         let _ = output.write_char('\n');
@@ -80,7 +135,7 @@ impl Renderer {
         let mut column_index = 0;
         for content in cells {
             let max_width = column_widths[column_index];
-            let abs_width = max_width.saturating_sub(content.len()) + self.column_spacing;
+            let abs_width = max_width.saturating_sub(content.chars().count()) + self.column_spacing;
             let _ = output.write_fmt(format_args!("{}{}", content, " ".repeat(abs_width)));
             if column_index == cols.len() - 1 {
                 column_index = 0;
@@ -92,3 +147,56 @@ impl Renderer {
         return output;
     }
 }
+
+/// Renders rows one by one as they arrive. The column widths can not be derived
+/// from the data upfront, so they have to be given.
+pub(crate) struct LiveRenderer {
+    column_widths: Vec<usize>,
+    column_spacing: usize,
+    header_written: bool,
+}
+
+impl LiveRenderer {
+    pub(crate) fn new(column_widths: Vec<usize>) -> Self {
+        Self {
+            column_widths,
+            column_spacing: 4,
+            header_written: false,
+        }
+    }
+
+    fn line(&self, contents: &[String]) -> String {
+        let mut output = String::new();
+        for (index, content) in contents.iter().enumerate() {
+            let width = self.column_widths.get(index).copied().unwrap_or_default();
+            let abs_width = width.saturating_sub(content.chars().count()) + self.column_spacing;
+            output.push_str(content);
+            output.push_str(&" ".repeat(abs_width));
+        }
+        output
+    }
+
+    /// Render a single row, prefixed with the header for the very first row.
+    pub(crate) fn render_row<'a, RowType: TableRow<'a>>(&mut self, row: &RowType) -> String {
+        let mut output = String::new();
+        if !self.header_written {
+            output.push_str(&self.line(&row.columns().unwrap_or_default()));
+            output.push('\n');
+            self.header_written = true;
+        }
+        output.push_str(&self.line(&row.cells().unwrap_or_default()));
+        output
+    }
+
+    /// Like `render_row`, but as comma separated values.
+    pub(crate) fn render_csv_row<'a, RowType: TableRow<'a>>(&mut self, row: &RowType) -> String {
+        let mut output = String::new();
+        if !self.header_written {
+            output.push_str(&csv_line(&row.columns().unwrap_or_default()));
+            output.push('\n');
+            self.header_written = true;
+        }
+        output.push_str(&csv_line(&row.cells().unwrap_or_default()));
+        output
+    }
+}
@@ -3,10 +3,12 @@ package main
 import (
 	"encoding/json"
 	"encoding/xml"
+	"fmt"
 	"log"
 	"log/slog"
 	"net/http"
 	"os"
+	"strings"
 )
 
 var (
@@ -51,6 +53,59 @@ func mockHandleDataLua(w http.ResponseWriter, r *http.Request) {
 	}
 	page := r.FormValue("page")
 	switch page {
+	case "wGuest":
+		jsonResponse(w, PageResponse{page, map[string]any{}, loginSidSuccess})
+	case "log":
+		jsonResponse(w, PageResponse{
+			page,
+			map[string]any{
+				"log": []map[string]any{
+					{"date": "18.10.26", "time": "09:12:33", "msg": "Internet connection established successfully.", "group": "net"},
+					{"date": "17.10.26", "time": "03:00:10", "msg": "Forced disconnect by the provider.", "group": "net"},
+				},
+			},
+			loginSidSuccess,
+		})
+	case "kidPro":
+		jsonResponse(w, PageResponse{
+			page,
+			map[string]any{
+				"profiles": []map[string]any{
+					{"id": "filtprof1", "name": "Standard"},
+					{"id": "filtprof2", "name": "Kids"},
+				},
+			},
+			loginSidSuccess,
+		})
+	case "kidLis":
+		jsonResponse(w, PageResponse{
+			page,
+			map[string]any{
+				"devices": []map[string]any{
+					{"name": "laptop", "profile": "filtprof1"},
+					{"name": "tablet", "profile": "filtprof2"},
+				},
+			},
+			loginSidSuccess,
+		})
+	case "netCnt":
+		counter := map[string]any{
+			"BytesSentHigh":     "0",
+			"BytesSentLow":      "123456789",
+			"BytesReceivedHigh": "1",
+			"BytesReceivedLow":  "5",
+		}
+		jsonResponse(w, PageResponse{
+			page,
+			map[string]any{
+				"Today":     counter,
+				"Yesterday": counter,
+				"ThisWeek":  counter,
+				"ThisMonth": counter,
+				"LastMonth": counter,
+			},
+			loginSidSuccess,
+		})
 	case "reboot":
 		jsonResponse(w, PageResponse{
 			page,
@@ -65,10 +120,330 @@ func mockHandleDataLua(w http.ResponseWriter, r *http.Request) {
 	}
 }
 
+// The TR-064 interface usually listens on port 49000. Point FRITZ_TR064_URL at
+// this server to use it instead.
+var tr064Services = []string{
+	"DeviceConfig:1",
+	"DeviceInfo:1",
+	"UserInterface:1",
+	"Hosts:1",
+	"X_AVM-DE_HostFilter:1",
+	"WANIPConnection:1",
+	"WANCommonInterfaceConfig:1",
+	"WANDSLInterfaceConfig:1",
+	"WLANConfiguration:1",
+	"WLANConfiguration:2",
+	"WLANConfiguration:3",
+}
+
+func mockHandleTr64Desc(w http.ResponseWriter, r *http.Request) {
+	var services strings.Builder
+	for _, service := range tr064Services {
+		name := strings.Split(service, ":")[0]
+		fmt.Fprintf(&services,
+			"<service><serviceType>urn:dslforum-org:service:%s</serviceType>"+
+				"<serviceId>urn:%s1</serviceId>"+
+				"<controlURL>/upnp/control/%s</controlURL>"+
+				"<eventSubURL>/upnp/control/%s</eventSubURL>"+
+				"<SCPDURL>/%sSCPD.xml</SCPDURL></service>",
+			service, name, strings.ToLower(name), strings.ToLower(name), strings.ToLower(name))
+	}
+	w.Header().Set("Content-Type", "text/xml")
+	fmt.Fprintf(w, `<?xml version="1.0"?><root xmlns="urn:dslforum-org:device-1-0">`+
+		`<systemVersion><Display>154.07.57</Display></systemVersion>`+
+		`<device><deviceType>urn:dslforum-org:device:InternetGatewayDevice:1</deviceType>`+
+		`<friendlyName>FRITZ!Box 7590</friendlyName><modelName>FRITZ!Box 7590</modelName>`+
+		`<serviceList>%s</serviceList></device></root>`, services.String())
+}
+
+// Canned out arguments of the SOAP actions, by action name.
+var tr064Actions = map[string][][2]string{
+	"Reboot":            {},
+	"ForceTermination":  {},
+	"RequestConnection": {},
+	"GetExternalIPAddress": {{"NewExternalIPAddress", "203.0.113.7"}},
+	"GetStatusInfo": {
+		{"NewConnectionStatus", "Connected"},
+		{"NewUptime", "93784"},
+	},
+	"X_AVM-DE_GetDNSServer": {
+		{"NewIPv4DNSServer1", "198.51.100.1"},
+		{"NewIPv4DNSServer2", "198.51.100.2"},
+	},
+	"X_AVM-DE_GetExternalIPv6Address": {
+		{"NewExternalIPv6Address", "2001:db8::1"},
+		{"NewPrefixLength", "64"},
+	},
+	"X_AVM-DE_GetIPv6Prefix": {
+		{"NewIPv6Prefix", "2001:db8:7::"},
+		{"NewPrefixLength", "56"},
+	},
+	"X_AVM-DE_GetIPv6DNSServer": {{"NewIPv6DNSServer1", "2001:db8::53"}},
+	"GetInfo": {
+		{"NewEnable", "1"},
+		{"NewStatus", "Up"},
+		{"NewChannel", "6"},
+		{"NewSSID", "mock"},
+		{"NewBeaconType", "11i"},
+		{"NewStandard", "ax"},
+		// WANDSLInterfaceConfig shares the action name.
+		{"NewDataPath", "Interleaved"},
+		{"NewUpstreamCurrRate", "42000"},
+		{"NewDownstreamCurrRate", "250000"},
+		{"NewUpstreamMaxRate", "46000"},
+		{"NewDownstreamMaxRate", "281000"},
+		{"NewUpstreamNoiseMargin", "80"},
+		{"NewDownstreamNoiseMargin", "65"},
+		{"NewUpstreamAttenuation", "120"},
+		{"NewDownstreamAttenuation", "135"},
+		{"NewModulationType", "VDSL"},
+		{"NewStandardUsed", "G.993.2"},
+		// And so do DeviceInfo and UserInterface.
+		{"NewSoftwareVersion", "154.07.57"},
+		{"NewUpgradeAvailable", "1"},
+		{"NewX_AVM-DE_Version", "154.07.58"},
+		{"NewX_AVM-DE_InfoURL", "https://download.avm.de/fritzbox/fritzbox-7590/deutschland/fritz.os/info_de.txt"},
+	},
+	"GetStatisticsTotal": {
+		{"NewLinkRetrain", "2"},
+		{"NewErroredSecs", "17"},
+		{"NewSeverelyErroredSecs", "1"},
+		{"NewFECErrors", "12345"},
+		{"NewATUCFECErrors", "12"},
+		{"NewCRCErrors", "88"},
+		{"NewATUCCRCErrors", "3"},
+	},
+	"GetTotalAssociations": {{"NewTotalAssociations", "3"}},
+	"GetSecurityKeys":      {{"NewKeyPassphrase", "password"}},
+	"SetEnable":            {},
+	"GetPortMappingNumberOfEntries": {{"NewPortMappingNumberOfEntries", "1"}},
+	"GetGenericPortMappingEntry": {
+		{"NewRemoteHost", ""},
+		{"NewExternalPort", "443"},
+		{"NewProtocol", "TCP"},
+		{"NewInternalPort", "443"},
+		{"NewInternalClient", "192.168.178.30"},
+		{"NewEnabled", "1"},
+		{"NewPortMappingDescription", "https"},
+		{"NewLeaseDuration", "0"},
+	},
+	"AddPortMapping":    {},
+	"DeletePortMapping": {},
+	"GetSpecificHostEntry": {
+		{"NewIPAddress", "192.168.178.20"},
+		{"NewAddressSource", "DHCP"},
+		{"NewLeaseTimeRemaining", "86000"},
+		{"NewInterfaceType", "802.11"},
+		{"NewActive", "1"},
+		{"NewHostName", "laptop"},
+	},
+	"X_AVM-DE_SetHostNameByMACAddress": {},
+	"X_AVM-DE_WakeOnLANByMACAddress":   {},
+	"X_AVM-DE_DoUpdate": {
+		{"NewUpgradeAvailable", "1"},
+		{"NewX_AVM-DE_UpdateState", "Started"},
+	},
+	"DisallowWANAccessByIP": {},
+	"GetWANAccessByIP": {
+		{"NewDisallow", "0"},
+		{"NewWANAccess", "granted"},
+	},
+	"X_AVM-DE_GetOnlineMonitor": {
+		{"NewTotalNumberSyncGroups", "1"},
+		{"NewSyncGroupName", "sync_dsl"},
+		{"Newds_current_bps", "2500000,2400000,1200000,300000"},
+		{"Newus_current_bps", "40000,35000,12000,8000"},
+	},
+	"X_AVM-DE_GetHostListPath": {
+		{"NewX_AVM-DE_HostListPath", "/devicehostlist.lua?sid=" + loginSidSuccess},
+	},
+	"X_AVM-DE_GetMeshListPath": {
+		{"NewX_AVM-DE_MeshListPath", "/meshlist.lua?sid=" + loginSidSuccess},
+	},
+}
+
+func mockHandleTr064Control(w http.ResponseWriter, r *http.Request) {
+	// Every first request is challenged, the response is not verified.
+	if r.Header.Get("Authorization") == "" {
+		w.Header().Set("WWW-Authenticate", `Digest realm="F!Box SOAP-Auth", nonce="0123456789ABCDEF", algorithm=MD5, qop="auth"`)
+		w.WriteHeader(http.StatusUnauthorized)
+		return
+	}
+	soapAction := strings.Trim(r.Header.Get("SOAPAction"), `"`)
+	_, action, _ := strings.Cut(soapAction, "#")
+	out, ok := tr064Actions[action]
+	w.Header().Set("Content-Type", "text/xml")
+	if !ok {
+		w.WriteHeader(http.StatusInternalServerError)
+		fmt.Fprint(w, `<?xml version="1.0"?><s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/">`+
+			`<s:Body><s:Fault><faultcode>s:Client</faultcode><faultstring>UPnPError</faultstring>`+
+			`<detail><UPnPError xmlns="urn:dslforum-org:control-1-0"><errorCode>401</errorCode>`+
+			`<errorDescription>Invalid Action</errorDescription></UPnPError></detail></s:Fault></s:Body></s:Envelope>`)
+		return
+	}
+	var args strings.Builder
+	for _, pair := range out {
+		fmt.Fprintf(&args, "<%s>%s</%s>", pair[0], pair[1], pair[0])
+	}
+	fmt.Fprintf(w, `<?xml version="1.0"?><s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" `+
+		`s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/"><s:Body>`+
+		`<u:%sResponse xmlns:u="%s">%s</u:%sResponse></s:Body></s:Envelope>`,
+		action, strings.Split(soapAction, "#")[0], args.String(), action)
+}
+
+// Every service claims all the canned actions, which is good enough for listing.
+func mockHandleScpd(w http.ResponseWriter, r *http.Request) {
+	var actions strings.Builder
+	for action, out := range tr064Actions {
+		fmt.Fprintf(&actions, "<action><name>%s</name><argumentList>", action)
+		for _, pair := range out {
+			fmt.Fprintf(&actions, "<argument><name>%s</name><direction>out</direction></argument>", pair[0])
+		}
+		actions.WriteString("</argumentList></action>")
+	}
+	w.Header().Set("Content-Type", "text/xml")
+	fmt.Fprintf(w, `<?xml version="1.0"?><scpd xmlns="urn:dslforum-org:service-1-0">`+
+		`<actionList>%s</actionList></scpd>`, actions.String())
+}
+
+func mockHandleDeviceHostList(w http.ResponseWriter, r *http.Request) {
+	type Item struct {
+		IPAddress     string
+		MACAddress    string
+		Active        int
+		HostName      string
+		InterfaceType string
+	}
+	type List struct {
+		Items []Item `xml:"Item"`
+	}
+	xmlResponse(w, List{[]Item{
+		{"192.168.178.20", "AA:BB:CC:DD:EE:01", 1, "laptop", "802.11"},
+		{"192.168.178.30", "AA:BB:CC:DD:EE:02", 0, "printer", "Ethernet"},
+	}})
+}
+
+func mockMeshLink(uid, from, to, typ string) map[string]any {
+	return map[string]any{
+		"uid": uid, "type": typ, "state": "CONNECTED",
+		"node_1_uid": from, "node_2_uid": to,
+		"cur_data_rate_rx": 866000, "cur_data_rate_tx": 650000,
+	}
+}
+
+func mockMeshNode(uid, name, model, mac, role string, links ...map[string]any) map[string]any {
+	return map[string]any{
+		"uid": uid, "device_name": name, "device_model": model,
+		"device_mac_address": mac, "mesh_role": role,
+		"node_interfaces": []map[string]any{
+			{"uid": "ni-" + uid, "mac_address": mac, "node_links": links},
+		},
+	}
+}
+
+func mockHandleMeshList(w http.ResponseWriter, r *http.Request) {
+	boxToRepeater := mockMeshLink("nl-1", "n-1", "n-2", "LAN")
+	repeaterToLaptop := mockMeshLink("nl-2", "n-2", "n-3", "WLAN")
+	jsonResponse(w, map[string]any{
+		"schema_version": "5.6.1",
+		"nodes": []map[string]any{
+			mockMeshNode("n-1", "fritz.box", "FRITZ!Box 7590", "AA:BB:CC:00:00:01", "master", boxToRepeater),
+			mockMeshNode("n-2", "repeater", "FRITZ!Repeater 2400", "AA:BB:CC:00:00:10", "slave", boxToRepeater, repeaterToLaptop),
+			mockMeshNode("n-3", "laptop", "", "AA:BB:CC:DD:EE:01", "unknown", repeaterToLaptop),
+		},
+	})
+}
+
+var smarthomeSwitchState = "1"
+
+func mockHandleHomeautoswitchLua(w http.ResponseWriter, r *http.Request) {
+	w.Header().Set("Content-Type", "text/xml")
+	switch r.FormValue("switchcmd") {
+	case "getdevicelistinfos":
+		fmt.Fprintf(w, `<devicelist version="1">`+
+			`<device identifier="08761 0000434" id="17" functionbitmask="35712" productname="FRITZ!DECT 200">`+
+			`<present>1</present><name>Plug</name><switch><state>%s</state></switch>`+
+			`<powermeter><power>12340</power><energy>4567</energy></powermeter>`+
+			`<temperature><celsius>225</celsius></temperature></device>`+
+			`<device identifier="09995 0123456" id="18" functionbitmask="320" productname="FRITZ!DECT 301">`+
+			`<present>1</present><name>Thermostat</name><temperature><celsius>210</celsius></temperature>`+
+			`<hkr><tist>42</tist><tsoll>44</tsoll></hkr></device></devicelist>`, smarthomeSwitchState)
+	case "setswitchon":
+		smarthomeSwitchState = "1"
+		fmt.Fprintln(w, smarthomeSwitchState)
+	case "setswitchoff":
+		smarthomeSwitchState = "0"
+		fmt.Fprintln(w, smarthomeSwitchState)
+	case "setswitchtoggle":
+		if smarthomeSwitchState == "1" {
+			smarthomeSwitchState = "0"
+		} else {
+			smarthomeSwitchState = "1"
+		}
+		fmt.Fprintln(w, smarthomeSwitchState)
+	case "sethkrtsoll":
+		fmt.Fprintln(w, r.FormValue("param"))
+	default:
+		w.WriteHeader(http.StatusBadRequest)
+	}
+}
+
+func mockHandleFoncallsListLua(w http.ResponseWriter, r *http.Request) {
+	w.Header().Set("Content-Type", "text/csv")
+	fmt.Fprint(w, "sep=;\n"+
+		"Typ;Datum;Name;Rufnummer;Nebenstellenbezeichnung;Eigene Rufnummer;Dauer\n"+
+		"2;18.10.26 09:15;Alice;0301234567;;Internet: 5678;0:00\n"+
+		"1;17.10.26 18:02;Bob;0409876;Telefon;Internet: 5678;0:12\n"+
+		"3;01.09.26 08:00;;0891111;Telefon;Internet: 5678;1:05\n")
+}
+
+// Exports a fixed file, imports accept anything.
+func mockHandleFirmwareCfg(w http.ResponseWriter, r *http.Request) {
+	if err := r.ParseMultipartForm(1 << 20); err != nil {
+		http.Error(w, err.Error(), http.StatusBadRequest)
+		return
+	}
+	if _, ok := r.MultipartForm.Value["ConfigExport"]; ok {
+		w.Header().Set("Content-Type", "application/octet-stream")
+		fmt.Fprint(w, "**** FRITZ!Box 7590 CONFIGURATION EXPORT\n"+
+			"Password=$$$$MOCK\n"+
+			"**** END OF EXPORT 00000000 ****\n")
+		return
+	}
+	w.Header().Set("Content-Type", "text/html")
+	fmt.Fprint(w, "<html><body>Import ok</body></html>")
+}
+
+// A DynDNS2 provider to point `fritz ddns --url` at, it knows a single host.
+func mockHandleNicUpdate(w http.ResponseWriter, r *http.Request) {
+	w.Header().Set("Content-Type", "text/plain")
+	if _, _, ok := r.BasicAuth(); !ok {
+		fmt.Fprint(w, "badauth")
+		return
+	}
+	if r.URL.Query().Get("hostname") != "home.example.org" {
+		fmt.Fprint(w, "nohost")
+		return
+	}
+	fmt.Fprintf(w, "good %s", r.URL.Query().Get("myip"))
+}
+
 func main() {
 	slog := slog.New(slog.NewTextHandler(os.Stdout, &slog.HandlerOptions{AddSource: true}))
 	http.HandleFunc("/login_sid.lua", mockHandleLoginSidLua)
 	http.HandleFunc("/data.lua", mockHandleDataLua)
+	http.HandleFunc("/tr64desc.xml", mockHandleTr64Desc)
+	http.HandleFunc("/upnp/control/", mockHandleTr064Control)
+	http.HandleFunc("/devicehostlist.lua", mockHandleDeviceHostList)
+	http.HandleFunc("/meshlist.lua", mockHandleMeshList)
+	http.HandleFunc("/webservices/homeautoswitch.lua", mockHandleHomeautoswitchLua)
+	http.HandleFunc("/fon_num/foncalls_list.lua", mockHandleFoncallsListLua)
+	http.HandleFunc("/cgi-bin/firmwarecfg", mockHandleFirmwareCfg)
+	http.HandleFunc("/nic/update", mockHandleNicUpdate)
+	for _, service := range tr064Services {
+		name := strings.Split(service, ":")[0]
+		http.HandleFunc("/"+strings.ToLower(name)+"SCPD.xml", mockHandleScpd)
+	}
 	addr := ":8000"
 	slog.Info("Starting at : ", "Address", addr)
 	log.Fatal(http.ListenAndServe(addr, nil))